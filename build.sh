@@ -1,5 +1,6 @@
-
 cargo build --release
 mv target/release/langin /usr/bin/langin
-
-
+# the compiler looks for the std library in `../lib/langin/std` relative to the binary
+mkdir -p /usr/lib/langin
+rm -rf /usr/lib/langin/std
+cp -r std /usr/lib/langin/std
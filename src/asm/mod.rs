@@ -1,5 +1,5 @@
 use crate::ast::*;
-use crate::escape;
+use crate::{escape, fold};
 use std::io::prelude::*;
 use std::io::BufWriter;
 use std::fs::File;
@@ -17,166 +17,566 @@ const REGISTERS: [&str; 6] = [
 
 pub struct CodeGen {
     buffer: BufWriter<File>,
-    variables: HashMap<String, (usize, usize)>,
+    // name, (stack offset, type)
+    variables: HashMap<String, (usize, Type)>,
+    // name, return type
+    functions: HashMap<String, Type>,
+    // name, number of fixed parameters of the variadic functions
+    variadic: HashMap<String, usize>,
+    globals: HashMap<String, Type>,
+    // labelled data of the globals, emitted after the strings
+    static_data: Vec<String>,
     strings: Vec<String>,
+    // label, continue block, break block of the enclosing loops
+    loops: Vec<(Option<String>, usize, usize)>,
     block_count: usize,
     stack_offset: usize,
     filename: String,
     current_fn: String,
 }
 
+// every variable gets a multiple of 8 bytes on the stack so values can be moved as qwords
+fn stack_size(var_t: &Type) -> usize {
+    var_t.size().div_ceil(8) * 8
+}
+
+// the items of a module are named `a.b::name`, labels cant contain `.` or `:`
+fn label(name: &str) -> String {
+    name.replace("::", "__").replace('.', "_")
+}
+
 fn get_filename(file: &str) -> Result<&str, Box<dyn std::error::Error>> {
-    return Ok(file.split(".").nth(0).ok_or::<Box<dyn std::error::Error>>("failed to parse filename".into())?);
+    file.split(".").next().ok_or::<Box<dyn std::error::Error>>("failed to parse filename".into())
 }
 
 impl CodeGen {
     pub fn new(filename: &str) -> Result<CodeGen, Box<dyn std::error::Error>> {
         let output_filename = format!("{}.asm", get_filename(filename)?);
-        return Ok(CodeGen {
+        Ok(CodeGen {
             buffer: BufWriter::new(File::create(&output_filename)?),
             variables: HashMap::new(),
+            functions: HashMap::new(),
+            variadic: HashMap::new(),
+            globals: HashMap::new(),
+            static_data: Vec::new(),
             strings: Vec::new(),
+            loops: Vec::new(),
             block_count: 1,
             stack_offset: 0,
             filename: output_filename,
             current_fn: String::new(),
-        });
+        })
     }
 
     fn entry(&mut self) -> Result<(), Box<dyn std::error::Error>> {
-        self.buffer.write(b"format ELF64 executable 3\nsegment readable executable\n")?;
-        return Ok(());
+        self.buffer.write_all(b"format ELF64 executable 3\nsegment readable executable\n")?;
+        Ok(())
     }
 
     fn exit(&mut self) -> Result<(), Box<dyn std::error::Error>> {
-        self.buffer.write(b"entry start\n")?;
-        self.buffer.write(b"start:\n")?;
-        self.buffer.write(b"    call main\n")?;
-        self.buffer.write(b"    mov rdi, rax\n")?;
-        self.buffer.write(b"    mov rax, 60\n")?;
-        self.buffer.write(b"    syscall\n")?;
-        self.buffer.write(b"segment readable writeable\n")?;
+        self.buffer.write_all(b"entry start\n")?;
+        self.buffer.write_all(b"start:\n")?;
+        self.buffer.write_all(b"    call main\n")?;
+        self.buffer.write_all(b"    mov rdi, rax\n")?;
+        self.buffer.write_all(b"    mov rax, 60\n")?;
+        self.buffer.write_all(b"    syscall\n")?;
+        self.buffer.write_all(b"segment readable writeable\n")?;
         for (index, value) in self.strings.iter().enumerate() {
-            write!(self.buffer, "str_{} db \"{}\", 0", index, escape::output_string_asm(value))?;
+            writeln!(self.buffer, "str_{} db \"{}\", 0", index, escape::output_string_asm(value))?;
+        }
+        for data in &self.static_data {
+            writeln!(self.buffer, "{}", data)?;
         }
-        return Ok(());
+        Ok(())
     }
 
     fn value(&mut self, value: &Value) -> Result<(String, String), Box<dyn std::error::Error>> {
-        return match value {
+        match value {
             Value::FunctionCall {name, params, ..} => {
-                self.buffer.write(b"    ;; -- FUNCTION CALL --\n")?;
+                self.buffer.write_all(b"    ;; -- FUNCTION CALL --\n")?;
                 let mut parameter_values: Vec<usize> = Vec::new();
                 for parameter in params.iter() {
                     let val = self.value(parameter)?;
                     let val_offset = self.val_is_on_stack(val)?;
                     parameter_values.push(val_offset);
                 }
-                for (index, param) in parameter_values.iter().enumerate() {
-                    write!(self.buffer, "    mov {}, [rbp-{}]\n", REGISTERS[index], param)?;
+
+                // the extra arguments of a variadic function are always on the stack so they can be walked
+                let registers = match self.variadic.get(name) {
+                    Some(fixed) => (*fixed).min(REGISTERS.len()),
+                    None => REGISTERS.len(),
+                };
+                // the stack has to be aligned to 16 bytes after the parameters are pushed
+                let stack_params = parameter_values.len().saturating_sub(registers);
+                let frame_size = self.stack_offset.next_multiple_of(16) + (stack_params % 2) * 8;
+                writeln!(self.buffer, "    sub rsp, {}", frame_size)?;
+                // parameters after the sixth are pushed in reverse order
+                for param in parameter_values.iter().skip(registers).rev() {
+                    writeln!(self.buffer, "    push qword [rbp-{}]", param)?;
+                }
+                for (index, param) in parameter_values.iter().take(registers).enumerate() {
+                    writeln!(self.buffer, "    mov {}, [rbp-{}]", REGISTERS[index], param)?;
                 }
-                write!(self.buffer, "    sub rsp, {}\n", self.stack_offset)?;
-                write!(self.buffer, "    call {}\n", name)?;
-                write!(self.buffer, "    add rsp, {}\n", self.stack_offset)?;
-                return Ok((format!("rax"), "reg".to_string()));
+                writeln!(self.buffer, "    call {}", label(name))?;
+                writeln!(self.buffer, "    add rsp, {}", frame_size + stack_params * 8)?;
+                Ok(("rax".to_string(), "reg".to_string()))
             },
             Value::BinaryExpr {l_expr, r_expr, op, ..} => {
                 let old_stack_offset = self.stack_offset;
-                let l_val = self.value(&l_expr)?;
-                let r_val = self.value(&r_expr)?;
-                self.buffer.write(b"    ;; -- BINARY EXPRESSION --\n")?;
+                // the left value has to be on the stack before the right value is generated
+                let l_val = self.value(l_expr)?;
                 let l_offset = self.val_is_on_stack(l_val)?;
+                let r_val = self.value(r_expr)?;
                 let r_offset = self.val_is_on_stack(r_val)?;
-                write!(self.buffer, "    mov rax, [rbp-{}]\n", l_offset)?;
-                match op {
-                    Operator::Plus => {
-                        write!(self.buffer, "    add rax, [rbp-{}]\n", r_offset)?;
-                    },
-                    Operator::Minus => {
-                        write!(self.buffer, "    sub rax, [rbp-{}]\n", r_offset)?;
-                    },
-                    Operator::Multiplication => {
-                        write!(self.buffer, "    mov rbx, [rbp-{}]\n", r_offset)?;
-                        write!(self.buffer, "    mul rbx\n")?;
-                    },
-                    Operator::Divide => {
-                        write!(self.buffer, "    mov rbx, [rbp-{}]\n", r_offset)?;
-                        write!(self.buffer, "    div rbx\n")?;
-                    },
+                self.buffer.write_all(b"    ;; -- BINARY EXPRESSION --\n")?;
+                let val_type = self.value_type(value);
+                let r_offset = self.scale(r_offset, &val_type)?;
+                writeln!(self.buffer, "    mov rax, [rbp-{}]", l_offset)?;
+                self.operation(op, r_offset, &val_type)?;
+                if let Type::TypedPtr(pointee_t) = self.value_type(r_expr) {
+                    // the difference of two pointers is counted in elements
+                    if pointee_t.size() != 1 {
+                        writeln!(self.buffer, "    mov rbx, {}", pointee_t.size())?;
+                        self.buffer.write_all(b"    cqo\n")?;
+                        self.buffer.write_all(b"    idiv rbx\n")?;
+                    }
                 }
                 self.stack_offset = old_stack_offset;
-                return Ok((format!("rax"), "reg".to_string()));
+                Ok(("rax".to_string(), "reg".to_string()))
             },
-            Value::Ref(value) => {
-                self.buffer.write(b"    ;; -- REFERENCE --\n")?;
-                let value = self.value(&value)?;
-                write!(self.buffer, "    lea rax, {}\n", value.0)?;
-                return Ok(("rax".to_string(), "reg".to_string()));
+            Value::Unary {value: operand, op: op @ (UnaryOp::Neg | UnaryOp::BitNot), ..} => {
+                let operand = self.value(operand)?;
+                let val_reg = self.val_is_in_reg(operand)?;
+                self.buffer.write_all(b"    ;; -- UNARY --\n")?;
+                if val_reg != "rax" {
+                    writeln!(self.buffer, "    mov rax, {}", val_reg)?;
+                }
+                writeln!(self.buffer, "    {} rax", if *op == UnaryOp::Neg { "neg" } else { "not" })?;
+                let val_type = self.value_type(value);
+                self.extend(&val_type)?;
+                Ok(("rax".to_string(), "reg".to_string()))
             },
-            Value::Deref(value, _) => {
-                self.buffer.write(b"    ;; -- DEREFERENCE --\n")?;
-                let value = self.value(&value)?;
+            Value::Comparison {..} | Value::Logical {..} | Value::Unary {..} => {
+                self.buffer.write_all(b"    ;; -- CONDITION --\n")?;
+                self.condition_value(value)
+            },
+            Value::Ref {value, ..} => {
+                self.buffer.write_all(b"    ;; -- REFERENCE --\n")?;
+                self.address(value)?;
+                Ok(("rax".to_string(), "reg".to_string()))
+            },
+            Value::Deref {value, deref_t: deref_type, ..} => {
+                self.buffer.write_all(b"    ;; -- DEREFERENCE --\n")?;
+                let value = self.value(value)?;
                 if &value.0 != "rax" {
-                    write!(self.buffer, "    mov rax, {}\n", value.0)?;
+                    writeln!(self.buffer, "    mov rax, {}", value.0)?;
+                }
+                self.load(deref_type)?;
+                Ok(("rbx".to_string(), "reg".to_string()))
+            },
+            Value::Field {..} | Value::Index {..} | Value::Load {..} => {
+                self.buffer.write_all(b"    ;; -- ELEMENT --\n")?;
+                self.address(value)?;
+                let elem_t = self.value_type(value);
+                if elem_t.is_aggregate() {
+                    // nested arrays decay to their address
+                    return Ok(("rax".to_string(), "reg".to_string()));
                 }
-                write!(self.buffer, "    mov rbx, [rax]\n")?;
-                return Ok(("rbx".to_string(), "reg".to_string()));
+                self.load(&elem_t)?;
+                Ok(("rbx".to_string(), "reg".to_string()))
             },
-            Value::Cast(value, _) => {
-                let value = self.value(&value)?;
-                Ok(value)
+            Value::Cast {value, cast_t: cast_type, ..} => {
+                let value = self.value(value)?;
+                if cast_type.size() == 8 {
+                    return Ok(value);
+                }
+                let val_reg = self.val_is_in_reg(value)?;
+                if val_reg != "rax" {
+                    writeln!(self.buffer, "    mov rax, {}", val_reg)?;
+                }
+                if *cast_type == Type::Bool {
+                    self.buffer.write_all(b"    cmp rax, 0\n")?;
+                    self.buffer.write_all(b"    setne al\n")?;
+                }
+                self.extend(cast_type)?;
+                Ok(("rax".to_string(), "reg".to_string()))
             },
+            Value::Sizeof {size_t, ..} => Ok((format!("{}", size_t.size()), "integer".to_string())),
+            Value::Variant {..} => Ok((format!("{}", fold::evaluate(value).expect("internal compiler error")), "integer".to_string())),
             Value::Int(integer) => Ok((format!("{}", integer), "integer".to_string())),
+            Value::Bool(boolean) => Ok((format!("{}", *boolean as usize), "integer".to_string())),
             Value::Str(string) => {
                 self.strings.push(string.clone());
                 Ok((format!("str_{}", self.strings.len() - 1), "string".to_string()))
             },
-            Value::Ident(ident) => {
-                let var = self.variables.get(ident).unwrap();
-                Ok((format!("[rbp-{}]", var.0), format!("{}", var.0)))
+            Value::Ident {name: ident, ..} => {
+                let var_t = self.value_type(value);
+                if var_t.is_aggregate() {
+                    // arrays decay to a pointer to their first element
+                    self.address(value)?;
+                    return Ok(("rax".to_string(), "reg".to_string()));
+                } else if let Some(var) = self.variables.get(ident) {
+                    return Ok((format!("[rbp-{}]", var.0), format!("{}", var.0)));
+                }
+                // globals are loaded from the writable segment
+                self.address(value)?;
+                self.load(&var_t)?;
+                Ok(("rbx".to_string(), "reg".to_string()))
             },
             Value::Null => Ok(("0x0".to_string(), "NULL".to_string())),
+        }
+    }
+
+    // evaluates the constant value of a global, the typechecker only allows literals
+    fn constant(&mut self, value: &Value, value_t: &Type) -> Result<String, Box<dyn std::error::Error>> {
+        if let Value::Str(string) = value {
+            self.strings.push(string.clone());
+            return Ok(format!("str_{}", self.strings.len() - 1));
+        }
+        let constant = match value {
+            Value::Bool(boolean) => *boolean as usize,
+            _ => fold::evaluate(value).ok_or("cant evaluate the value of a global")?,
         };
+        // only the bytes of the type are stored
+        let bits = value_t.size() * 8;
+        Ok(format!("{}", if bits < 64 { constant & ((1 << bits) - 1) } else { constant }))
+    }
+
+    // leaves the address of a variable, dereference or field in rax
+    fn address(&mut self, value: &Value) -> Result<(), Box<dyn std::error::Error>> {
+        match value {
+            Value::Ident {name: ident, ..} => {
+                if let Some(var) = self.variables.get(ident) {
+                    writeln!(self.buffer, "    lea rax, [rbp-{}]", var.0)?;
+                } else {
+                    writeln!(self.buffer, "    lea rax, [global_{}]", label(ident))?;
+                }
+            },
+            Value::Deref {value: ptr, ..} | Value::Load {value: ptr, ..} => {
+                let ptr = self.value(ptr)?;
+                let ptr_reg = self.val_is_in_reg(ptr)?;
+                if ptr_reg != "rax" {
+                    writeln!(self.buffer, "    mov rax, {}", ptr_reg)?;
+                }
+            },
+            Value::Field {value: base, field, ..} => {
+                self.address(base)?;
+                let offset = match self.value_type(base) {
+                    Type::Struct(struct_t) => struct_t.field(field).expect("internal compiler error").0,
+                    _ => panic!("internal compiler error"),
+                };
+                if offset != 0 {
+                    writeln!(self.buffer, "    add rax, {}", offset)?;
+                }
+            },
+            Value::Index {value: base, index, ..} => {
+                let elem_size = self.value_type(value).size();
+                if let Value::Int(integer) = index.as_ref() {
+                    // constant indices are folded into the offset
+                    self.base_address(base)?;
+                    if *integer != 0 {
+                        writeln!(self.buffer, "    add rax, {}", integer * elem_size)?;
+                    }
+                    return Ok(());
+                }
+                let old_stack_offset = self.stack_offset;
+                let index = self.value(index)?;
+                let index_offset = self.val_is_on_stack(index)?;
+                self.base_address(base)?;
+                writeln!(self.buffer, "    mov rbx, [rbp-{}]", index_offset)?;
+                if elem_size != 1 {
+                    writeln!(self.buffer, "    imul rbx, rbx, {}", elem_size)?;
+                }
+                self.buffer.write_all(b"    add rax, rbx\n")?;
+                self.stack_offset = old_stack_offset;
+            },
+            _ => panic!("internal compiler error"),
+        }
+        Ok(())
+    }
+
+    // arrays are indexed from their address, pointers from their value
+    fn base_address(&mut self, base: &Value) -> Result<(), Box<dyn std::error::Error>> {
+        if let Type::TypedPtr(_) = self.value_type(base) {
+            let ptr = self.value(base)?;
+            let ptr_reg = self.val_is_in_reg(ptr)?;
+            if ptr_reg != "rax" {
+                writeln!(self.buffer, "    mov rax, {}", ptr_reg)?;
+            }
+            return Ok(());
+        }
+        self.address(base)
+    }
+
+    // load a value of the type from the address in rax into rbx
+    fn load(&mut self, value_t: &Type) -> Result<(), Box<dyn std::error::Error>> {
+        writeln!(self.buffer, "    {}", match value_t {
+            Type::U8 | Type::Bool => "movzx rbx, byte [rax]",
+            Type::I8 => "movsx rbx, byte [rax]",
+            Type::U16 => "movzx rbx, word [rax]",
+            Type::I16 => "movsx rbx, word [rax]",
+            Type::U32 => "mov ebx, dword [rax]",
+            Type::I32 => "movsxd rbx, dword [rax]",
+            _ => "mov rbx, [rax]",
+        })?;
+        Ok(())
+    }
+
+    // store rbx to the address in rax, only the bytes of the type are written
+    fn store(&mut self, value_t: &Type) -> Result<(), Box<dyn std::error::Error>> {
+        writeln!(self.buffer, "    {}", match value_t.size() {
+            1 => "mov byte [rax], bl",
+            2 => "mov word [rax], bx",
+            4 => "mov dword [rax], ebx",
+            _ => "mov [rax], rbx",
+        })?;
+        Ok(())
+    }
+
+    // offsets added to a typed pointer are counted in elements of the pointee, the scaled
+    // offset is put in a new temporary so a variable used as the offset is left alone
+    fn scale(&mut self, offset: usize, val_type: &Type) -> Result<usize, Box<dyn std::error::Error>> {
+        if let Type::TypedPtr(pointee_t) = val_type {
+            if pointee_t.size() != 1 {
+                writeln!(self.buffer, "    mov rcx, [rbp-{}]", offset)?;
+                writeln!(self.buffer, "    imul rcx, rcx, {}", pointee_t.size())?;
+                return self.val_is_on_stack(("rcx".to_string(), "reg".to_string()));
+            }
+        }
+        Ok(offset)
+    }
+
+    // rax = rax op [rbp-r_offset], the result is wrapped around to the width of the type
+    fn operation(&mut self, op: &Operator, r_offset: usize, val_type: &Type) -> Result<(), Box<dyn std::error::Error>> {
+        match op {
+            Operator::Plus => {
+                writeln!(self.buffer, "    add rax, [rbp-{}]", r_offset)?;
+            },
+            Operator::Minus => {
+                writeln!(self.buffer, "    sub rax, [rbp-{}]", r_offset)?;
+            },
+            Operator::Multiplication => {
+                writeln!(self.buffer, "    mov rbx, [rbp-{}]", r_offset)?;
+                writeln!(self.buffer, "    {} rbx", if val_type.is_signed() { "imul" } else { "mul" })?;
+            },
+            Operator::Divide | Operator::Modulo => {
+                writeln!(self.buffer, "    mov rbx, [rbp-{}]", r_offset)?;
+                if val_type.is_signed() {
+                    writeln!(self.buffer, "    cqo")?;
+                    writeln!(self.buffer, "    idiv rbx")?;
+                } else {
+                    writeln!(self.buffer, "    xor rdx, rdx")?;
+                    writeln!(self.buffer, "    div rbx")?;
+                }
+                if *op == Operator::Modulo {
+                    // the remainder is left in rdx
+                    writeln!(self.buffer, "    mov rax, rdx")?;
+                }
+            },
+            Operator::BitAnd => {
+                writeln!(self.buffer, "    and rax, [rbp-{}]", r_offset)?;
+            },
+            Operator::BitOr => {
+                writeln!(self.buffer, "    or rax, [rbp-{}]", r_offset)?;
+            },
+            Operator::BitXor => {
+                writeln!(self.buffer, "    xor rax, [rbp-{}]", r_offset)?;
+            },
+            Operator::ShiftLeft | Operator::ShiftRight => {
+                writeln!(self.buffer, "    mov rcx, [rbp-{}]", r_offset)?;
+                let shift = match (op, val_type.is_signed()) {
+                    (Operator::ShiftLeft, _) => "shl",
+                    (_, true) => "sar",
+                    (_, false) => "shr",
+                };
+                writeln!(self.buffer, "    {} rax, cl", shift)?;
+            },
+        }
+        self.extend(val_type)?;
+        Ok(())
+    }
+
+    // values are kept zero or sign extended to 64 bits while they are in registers
+    fn extend(&mut self, value_t: &Type) -> Result<(), Box<dyn std::error::Error>> {
+        let extend = match value_t {
+            Type::U8 | Type::Bool => "movzx rax, al",
+            Type::I8 => "movsx rax, al",
+            Type::U16 => "movzx rax, ax",
+            Type::I16 => "movsx rax, ax",
+            Type::U32 => "mov eax, eax",
+            Type::I32 => "movsxd rax, eax",
+            _ => return Ok(()),
+        };
+        writeln!(self.buffer, "    {}", extend)?;
+        Ok(())
+    }
+
+    // locals are a whole qword, values stored in them are wrapped around to the width of their type
+    fn narrow(&mut self, val_reg: String, var_t: &Type) -> Result<String, Box<dyn std::error::Error>> {
+        if !var_t.is_integer() || var_t.size() == 8 {
+            return Ok(val_reg);
+        } else if val_reg != "rax" {
+            writeln!(self.buffer, "    mov rax, {}", val_reg)?;
+        }
+        self.extend(var_t)?;
+        Ok("rax".to_string())
     }
 
-    fn comparison(&mut self, comp: &Comparison) -> Result<String, Box<dyn std::error::Error>> {
-        let old_stack_offset = self.stack_offset;
-        let l_val = self.value(&comp.l_expr)?;
-        let r_val = self.value(&comp.r_expr)?;
-        self.buffer.write(b"    ;; -- COMPARISON --\n")?;
-        // make sure value is on the stack
-        let l_offset = self.val_is_on_stack(l_val)?;
-        let r_offset = self.val_is_on_stack(r_val)?;
-        write!(self.buffer, "    mov rax, [rbp-{}]\n", l_offset)?;
-        write!(self.buffer, "    cmp rax, [rbp-{}]\n", r_offset)?;
-        self.stack_offset = old_stack_offset;
-        return match comp.op {
-            ComparisonOp::Equal => Ok("jne".to_string()),
-            ComparisonOp::NotEqual => Ok("je".to_string()),
-            ComparisonOp::Bigger => Ok("jle".to_string()),
-            ComparisonOp::Smaller => Ok("jg".to_string()),
+    // mirrors the typechecker, integer literals take the type of the other side
+    fn common_type(&self, l_expr: &Value, r_expr: &Value) -> Type {
+        let l_type = self.value_type(l_expr);
+        let r_type = self.value_type(r_expr);
+        if l_expr.is_literal() && r_expr.is_literal() {
+            return if l_type.is_signed() || r_type.is_signed() { Type::I64 } else { Type::Int };
+        } else if l_expr.is_literal() && r_type.is_integer() {
+            return r_type;
+        } else if r_expr.is_literal() && l_type.is_integer() {
+            return l_type;
         }
+        l_type.common(&r_type).expect("internal compiler error")
+    }
+
+    // the ast is already typechecked so this only has to look up the resulting type
+    fn value_type(&self, value: &Value) -> Type {
+        match value {
+            Value::BinaryExpr {l_expr, op: Operator::ShiftLeft | Operator::ShiftRight, ..} => self.value_type(l_expr),
+            Value::BinaryExpr {l_expr, r_expr, ..} => match (self.value_type(l_expr), self.value_type(r_expr)) {
+                (Type::Ptr | Type::TypedPtr(_), Type::Ptr | Type::TypedPtr(_)) => Type::Int,
+                (pointer_t @ (Type::Ptr | Type::TypedPtr(_)), _) => pointer_t,
+                _ => self.common_type(l_expr, r_expr),
+            },
+            Value::Unary {value, op: UnaryOp::Neg, ..} if value.is_literal() => Type::I64,
+            Value::Unary {value, op: UnaryOp::Neg | UnaryOp::BitNot, ..} => self.value_type(value),
+            Value::Comparison {..} | Value::Logical {..} | Value::Unary {..} => Type::Bool,
+            Value::FunctionCall {name, ..} => self.functions.get(name).expect("internal compiler error").clone(),
+            Value::Cast {cast_t, ..} | Value::Deref {deref_t: cast_t, ..} => cast_t.clone(),
+            Value::Ref {value, ..} => Type::TypedPtr(Box::new(self.value_type(value))),
+            Value::Str(_) => Type::TypedPtr(Box::new(Type::U8)),
+            Value::Load {value, ..} => match self.value_type(value) {
+                Type::TypedPtr(pointee_t) => *pointee_t,
+                _ => panic!("internal compiler error"),
+            },
+            Value::Field {value, field, ..} => match self.value_type(value) {
+                Type::Struct(struct_t) => struct_t.field(field).expect("internal compiler error").1,
+                _ => panic!("internal compiler error"),
+            },
+            Value::Index {value, ..} => match self.value_type(value) {
+                Type::Array(elem_t, _) | Type::TypedPtr(elem_t) => *elem_t,
+                _ => panic!("internal compiler error"),
+            },
+            Value::Int(_) | Value::Sizeof {..} => Type::Int,
+            Value::Variant {enum_t, ..} => enum_t.clone(),
+            Value::Bool(_) => Type::Bool,
+            Value::Ident {name: ident, ..} => match self.variables.get(ident) {
+                Some(var) => var.1.clone(),
+                None => self.globals.get(ident).expect("internal compiler error").clone(),
+            },
+            Value::Null => Type::Void,
+        }
+    }
+
+    fn block(&mut self) -> usize {
+        self.block_count += 1;
+        self.block_count
+    }
+
+    // jumps to `label` if the condition evaluates to `jump_if` and falls through otherwise
+    fn comparison(&mut self, condition: &Value, label: usize, jump_if: bool) -> Result<(), Box<dyn std::error::Error>> {
+        match condition {
+            Value::Comparison {l_expr, r_expr, op, ..} => {
+                let old_stack_offset = self.stack_offset;
+                let l_val = self.value(l_expr)?;
+                let l_offset = self.val_is_on_stack(l_val)?;
+                let r_val = self.value(r_expr)?;
+                let r_offset = self.val_is_on_stack(r_val)?;
+                self.buffer.write_all(b"    ;; -- COMPARISON --\n")?;
+                writeln!(self.buffer, "    mov rax, [rbp-{}]", l_offset)?;
+                writeln!(self.buffer, "    cmp rax, [rbp-{}]", r_offset)?;
+                self.stack_offset = old_stack_offset;
+
+                // (jump if true, jump if false)
+                let signed = self.common_type(l_expr, r_expr).is_signed();
+                let jumps = match (op, signed) {
+                    (ComparisonOp::Equal, _) => ("je", "jne"),
+                    (ComparisonOp::NotEqual, _) => ("jne", "je"),
+                    (ComparisonOp::Bigger, false) => ("ja", "jbe"),
+                    (ComparisonOp::Smaller, false) => ("jb", "jae"),
+                    (ComparisonOp::BiggerEqual, false) => ("jae", "jb"),
+                    (ComparisonOp::SmallerEqual, false) => ("jbe", "ja"),
+                    (ComparisonOp::Bigger, true) => ("jg", "jle"),
+                    (ComparisonOp::Smaller, true) => ("jl", "jge"),
+                    (ComparisonOp::BiggerEqual, true) => ("jge", "jl"),
+                    (ComparisonOp::SmallerEqual, true) => ("jle", "jg"),
+                };
+                writeln!(self.buffer, "    {} BB_{}", if jump_if { jumps.0 } else { jumps.1 }, label)?;
+            },
+            Value::Logical {l_expr, r_expr, op, ..} => {
+                // short circuit, the right side is only evaluated if the left side doesnt decide the result
+                if (*op == LogicalOp::And) != jump_if {
+                    self.comparison(l_expr, label, jump_if)?;
+                    self.comparison(r_expr, label, jump_if)?;
+                } else {
+                    let skip_label = self.block();
+                    self.comparison(l_expr, skip_label, !jump_if)?;
+                    self.comparison(r_expr, label, jump_if)?;
+                    writeln!(self.buffer, "BB_{}:", skip_label)?;
+                }
+            },
+            Value::Unary {value, op: UnaryOp::Not, ..} => {
+                self.comparison(value, label, !jump_if)?;
+            },
+            _ => {
+                let old_stack_offset = self.stack_offset;
+                let value = self.value(condition)?;
+                if value.0 != "rax" {
+                    writeln!(self.buffer, "    mov rax, {}", value.0)?;
+                }
+                self.buffer.write_all(b"    ;; -- COMPARISON --\n")?;
+                self.buffer.write_all(b"    cmp rax, 0\n")?;
+                self.stack_offset = old_stack_offset;
+                writeln!(self.buffer, "    {} BB_{}", if jump_if { "jne" } else { "je" }, label)?;
+            },
+        }
+        Ok(())
+    }
+
+    // materialize a condition as 0 or 1 in rax
+    fn condition_value(&mut self, condition: &Value) -> Result<(String, String), Box<dyn std::error::Error>> {
+        let false_label = self.block();
+        let exit_label = self.block();
+        self.comparison(condition, false_label, false)?;
+        self.buffer.write_all(b"    mov rax, 1\n")?;
+        writeln!(self.buffer, "    jmp BB_{}", exit_label)?;
+        writeln!(self.buffer, "BB_{}:", false_label)?;
+        self.buffer.write_all(b"    mov rax, 0\n")?;
+        writeln!(self.buffer, "BB_{}:", exit_label)?;
+        Ok(("rax".to_string(), "reg".to_string()))
     }
 
     fn val_is_on_stack(&mut self, value: (String, String)) -> Result<usize, Box<dyn std::error::Error>> {
         let offset = if let Ok(integer) = value.1.parse::<usize>() {
             integer
         } else {
+            let val_reg = self.val_is_in_reg(value)?;
             self.stack_offset += 8;
-            write!(self.buffer, "    mov qword [rbp-{}], {}\n", self.stack_offset, value.0)?;
+            writeln!(self.buffer, "    mov qword [rbp-{}], {}", self.stack_offset, val_reg)?;
             self.stack_offset
         };
-        return Ok(offset);
+        Ok(offset)
     }
 
     fn val_is_in_reg(&mut self, value: (String, String)) -> Result<String, Box<dyn std::error::Error>> {
         let reg = if let Ok(addr) = value.1.parse::<usize>() {
-            write!(self.buffer, "    rax, [rbp-{}]\n", addr)?;
+            writeln!(self.buffer, "    mov rax, [rbp-{}]", addr)?;
+            String::from("rax")
+        } else if value.1 == "integer" && value.0.parse::<usize>()? > i32::MAX as usize {
+            // only 32 bit immediates can be moved into memory
+            writeln!(self.buffer, "    mov rax, {}", value.0)?;
             String::from("rax")
         } else {
             value.0
         };
-        return Ok(reg);
+        Ok(reg)
     }
 
     pub fn generate(&mut self, ast: &Vec<Ast>, entry: bool) -> Result<(), Box<dyn std::error::Error>> {
@@ -188,148 +588,333 @@ impl CodeGen {
 
         for instruction in ast {
             match instruction {
-                Ast::Function {name, param_t, body, ..} => {
+                Ast::Function {name, param_t, variadic, return_t, body, ..} => {
                     // preparation
                     let old_fn = self.current_fn.clone();
                     self.current_fn = name.clone();
+                    self.functions.insert(name.clone(), return_t.clone());
 
                     // stack frame preparation
-                    self.buffer.write(b"    ;; -- FUNCTION --\n")?;
-                    write!(self.buffer, "{}:\n", name)?;
-                    self.buffer.write(b"    push rbp\n")?;
-                    self.buffer.write(b"    mov rbp, rsp\n")?;
+                    self.buffer.write_all(b"    ;; -- FUNCTION --\n")?;
+                    writeln!(self.buffer, "{}:", label(name))?;
+                    self.buffer.write_all(b"    push rbp\n")?;
+                    self.buffer.write_all(b"    mov rbp, rsp\n")?;
 
-                    // load parameters onto the stack
+                    // load parameters onto the stack, parameters after the sixth are above the return address
                     for (index, parameter) in param_t.iter().enumerate() {
-                        self.stack_offset += parameter.1.size();
-                        write!(self.buffer, "    mov [rbp-{}], {}\n", self.stack_offset, REGISTERS[index])?;
-                        self.variables.insert(parameter.0.clone(), (self.stack_offset, parameter.1.size()));
+                        self.stack_offset += stack_size(&parameter.1);
+                        if index < REGISTERS.len() {
+                            writeln!(self.buffer, "    mov [rbp-{}], {}", self.stack_offset, REGISTERS[index])?;
+                        } else {
+                            writeln!(self.buffer, "    mov rax, [rbp+{}]", 16 + 8 * (index - REGISTERS.len()))?;
+                            writeln!(self.buffer, "    mov [rbp-{}], rax", self.stack_offset)?;
+                        }
+                        self.variables.insert(parameter.0.clone(), (self.stack_offset, parameter.1.clone()));
                         local_vars.push(parameter.0.clone());
                     }
+                    if let Some(variadic) = variadic {
+                        // the extra arguments follow the fixed parameters on the stack
+                        let extra_offset = 16 + 8 * param_t.len().saturating_sub(REGISTERS.len());
+                        self.stack_offset += 8;
+                        writeln!(self.buffer, "    lea rax, [rbp+{}]", extra_offset)?;
+                        writeln!(self.buffer, "    mov [rbp-{}], rax", self.stack_offset)?;
+                        self.variables.insert(variadic.clone(), (self.stack_offset, Type::TypedPtr(Box::new(Type::Int))));
+                        local_vars.push(variadic.clone());
+                        self.variadic.insert(name.clone(), param_t.len());
+                    }
 
                     // body
                     self.generate(body, false)?;
 
                     // return
-                    write!(self.buffer, "{}_ret:\n", name)?;
-                    self.buffer.write(b"    pop rbp\n")?;
-                    self.buffer.write(b"    ret\n")?;
+                    writeln!(self.buffer, "{}_ret:", label(name))?;
+                    self.buffer.write_all(b"    pop rbp\n")?;
+                    self.buffer.write_all(b"    ret\n")?;
 
                     // retreving old values from previous scope
                     self.current_fn = old_fn;
-                    local_vars = Vec::new();
+                    for var in local_vars.drain(..) {
+                        let var = self.variables.remove(&var).expect("internal compiler error");
+                        self.stack_offset -= stack_size(&var.1);
+                    }
                 },
                 Ast::Return {value, ..} => {
-                    self.buffer.write(b"    ;; -- RETURN --\n")?;
+                    self.buffer.write_all(b"    ;; -- RETURN --\n")?;
                     let value = self.value(value)?;
-                    write!(self.buffer, "    mov rax, {}\n", value.0)?;
-                    write!(self.buffer, "    jmp {}_ret\n", self.current_fn)?;
+                    writeln!(self.buffer, "    mov rax, {}", value.0)?;
+                    writeln!(self.buffer, "    jmp {}_ret", label(&self.current_fn))?;
+                },
+                Ast::Variable {name, var_t, value, ..} if entry => {
+                    // globals are static data with a constant value
+                    let data = match (value, var_t.size()) {
+                        (Value::Null, _) => format!("rb {}", var_t.size()),
+                        (_, 1) => format!("db {}", self.constant(value, var_t)?),
+                        (_, 2) => format!("dw {}", self.constant(value, var_t)?),
+                        (_, 4) => format!("dd {}", self.constant(value, var_t)?),
+                        _ => format!("dq {}", self.constant(value, var_t)?),
+                    };
+                    self.static_data.push(format!("global_{} {}", label(name), data));
+                    self.globals.insert(name.clone(), var_t.clone());
                 },
                 Ast::Variable {name, var_t, value, ..} => {
                     // stack preparation
-                    self.stack_offset += var_t.size();
-
-                    self.buffer.write(b"    ;; -- VARIABLE --\n")?;
+                    self.stack_offset += stack_size(var_t);
+
+                    self.buffer.write_all(b"    ;; -- VARIABLE --\n")?;
+                    if let (Value::Null, true) = (value, stack_size(var_t) > 8) {
+                        // zero the whole variable
+                        writeln!(self.buffer, "    lea rdi, [rbp-{}]", self.stack_offset)?;
+                        writeln!(self.buffer, "    mov rcx, {}", stack_size(var_t) / 8)?;
+                        self.buffer.write_all(b"    xor rax, rax\n")?;
+                        self.buffer.write_all(b"    rep stosq\n")?;
+                        self.variables.insert(name.clone(), (self.stack_offset, var_t.clone()));
+                        local_vars.push(name.clone());
+                        continue;
+                    }
                     let value = self.value(value)?;
 
                     // make sure value is in register before moving it onto the stack
                     let val_reg = self.val_is_in_reg(value)?;
-                    write!(self.buffer, "    mov qword [rbp-{}], {}\n", self.stack_offset, val_reg)?;
+                    let val_reg = self.narrow(val_reg, var_t)?;
+                    writeln!(self.buffer, "    mov qword [rbp-{}], {}", self.stack_offset, val_reg)?;
 
                     // append variables
-                    self.variables.insert(name.clone(), (self.stack_offset, var_t.size()));
+                    self.variables.insert(name.clone(), (self.stack_offset, var_t.clone()));
                     local_vars.push(name.clone());
                 },
                 Ast::MutateVar {name, value, ..} => {
-                    self.buffer.write(b"    ;; -- MUTATE VARIABLE --\n")?;
+                    self.buffer.write_all(b"    ;; -- MUTATE VARIABLE --\n")?;
                     let value = self.value(value)?;
 
                     // make sure value is in register before moving it onto the stack
                     let val_reg = self.val_is_in_reg(value)?;
-                    write!(self.buffer, "    mov qword [rbp-{}], {}\n", self.variables.get(name).expect("internal compiler error").0, val_reg)?;
+                    if let Some((offset, var_t)) = self.variables.get(name).cloned() {
+                        let val_reg = self.narrow(val_reg, &var_t)?;
+                        writeln!(self.buffer, "    mov qword [rbp-{}], {}", offset, val_reg)?;
+                    } else {
+                        // globals only have the bytes of their type
+                        if val_reg != "rbx" {
+                            writeln!(self.buffer, "    mov rbx, {}", val_reg)?;
+                        }
+                        writeln!(self.buffer, "    lea rax, [global_{}]", label(name))?;
+                        let var_t = self.globals.get(name).expect("internal compiler error").clone();
+                        self.store(&var_t)?;
+                    }
                 },
-                Ast::MutatePtr {ptr, value, ..} => {
-                    self.buffer.write(b"    ;; -- MUTATE POINTER --\n")?;
+                Ast::MutatePtr {ptr_type, ptr, value, ..} => {
+                    self.buffer.write_all(b"    ;; -- MUTATE POINTER --\n")?;
+                    let old_stack_offset = self.stack_offset;
                     let value = self.value(value)?;
-                    let ptr_val = self.value(ptr)?;
-
                     let val_loc = self.val_is_on_stack(value)?;
+
+                    let ptr_val = self.value(ptr)?;
                     let ptr_val_loc = self.val_is_on_stack(ptr_val)?;
 
-                    write!(self.buffer, "    mov rax, [rbp-{}]\n", ptr_val_loc)?;
-                    write!(self.buffer, "    mov rbx, [rbp-{}]\n", val_loc)?;
-                    write!(self.buffer, "    mov [rax], rbx\n")?;
+                    writeln!(self.buffer, "    mov rax, [rbp-{}]", ptr_val_loc)?;
+                    writeln!(self.buffer, "    mov rbx, [rbp-{}]", val_loc)?;
+                    self.store(ptr_type)?;
+                    self.stack_offset = old_stack_offset;
                 },
-                Ast::If {comparison, body, else_body, ..} => {
-                    self.block_count += 1;
-                    self.buffer.write(b"    ;; -- IF --\n")?;
+                Ast::Assign {target, value, ..} => {
+                    self.buffer.write_all(b"    ;; -- ASSIGN --\n")?;
+                    let old_stack_offset = self.stack_offset;
+                    let value = self.value(value)?;
+                    let val_loc = self.val_is_on_stack(value)?;
 
-                    // comparison
-                    let jump = self.comparison(comparison)?;
+                    self.address(target)?;
+                    writeln!(self.buffer, "    mov rbx, [rbp-{}]", val_loc)?;
+                    let target_t = self.value_type(target);
+                    self.store(&target_t)?;
+                    self.stack_offset = old_stack_offset;
+                },
+                Ast::Compound {target, op, value, ..} => {
+                    self.buffer.write_all(b"    ;; -- COMPOUND ASSIGN --\n")?;
+                    let old_stack_offset = self.stack_offset;
+                    let value = self.value(value)?;
+                    let val_offset = self.val_is_on_stack(value)?;
+
+                    // the address is only computed once
+                    self.address(target)?;
+                    let addr_offset = self.val_is_on_stack(("rax".to_string(), "reg".to_string()))?;
+                    let target_t = self.value_type(target);
+                    self.load(&target_t)?;
+                    let val_offset = self.scale(val_offset, &target_t)?;
+                    self.buffer.write_all(b"    mov rax, rbx\n")?;
+                    self.operation(op, val_offset, &target_t)?;
+
+                    self.buffer.write_all(b"    mov rbx, rax\n")?;
+                    writeln!(self.buffer, "    mov rax, [rbp-{}]", addr_offset)?;
+                    if matches!(target, Value::Ident {name, ..} if self.variables.contains_key(name)) {
+                        // locals are read as a whole qword so the extended value is stored
+                        self.buffer.write_all(b"    mov [rax], rbx\n")?;
+                    } else {
+                        self.store(&target_t)?;
+                    }
+                    self.stack_offset = old_stack_offset;
+                },
+                Ast::Struct {..} | Ast::Const {..} | Ast::Enum {..} => {},
+                Ast::If {condition, body, else_body, ..} => {
+                    let else_label = self.block();
+                    let exit_label = self.block();
+                    self.buffer.write_all(b"    ;; -- IF --\n")?;
 
-                    // jump to exit block if false
-                    write!(self.buffer, "    {} BB_{}\n", jump, self.block_count)?;
+                    // jump to else block if false
+                    self.comparison(condition, else_label, false)?;
 
                     // body
                     self.generate(body, false)?;
 
                     // jump to exit block
-                    write!(self.buffer, "    jmp BB_{}\n", self.block_count + 1)?;
-                    write!(self.buffer, "BB_{}:\n", self.block_count)?;
+                    writeln!(self.buffer, "    jmp BB_{}", exit_label)?;
+                    writeln!(self.buffer, "BB_{}:", else_label)?;
 
                     // else body
                     self.generate(else_body, false)?;
 
                     // exit block
-                    self.block_count += 1;
-                    write!(self.buffer, "BB_{}:\n", self.block_count)?;
+                    writeln!(self.buffer, "BB_{}:", exit_label)?;
                 },
-                Ast::While {comparison, body, ..} => {
-                    self.buffer.write(b"    ;; -- WHILE --\n")?;
+                Ast::While {label, condition, body, step, ..} => {
+                    let start_label = self.block();
+                    let exit_label = self.block();
+                    // continue jumps to the step if there is one
+                    let step_label = if step.is_empty() { start_label } else { self.block() };
+                    self.buffer.write_all(b"    ;; -- WHILE --\n")?;
 
                     // entry block
-                    self.block_count += 1;
-                    let start_label = self.block_count;
-                    write!(self.buffer, "BB_{}:\n", self.block_count)?;
-
-                    // comparison
-                    let jump = self.comparison(comparison)?;
+                    writeln!(self.buffer, "BB_{}:", start_label)?;
 
-                    self.block_count += 1;
-                    let exit_label = self.block_count;
-                    write!(self.buffer, "    {} BB_{}\n", jump, exit_label)?;
+                    // jump to exit block if false
+                    self.comparison(condition, exit_label, false)?;
 
                     // body
+                    self.loops.push((label.clone(), step_label, exit_label));
                     self.generate(body, false)?;
+                    self.loops.pop();
+
+                    // step block
+                    if !step.is_empty() {
+                        writeln!(self.buffer, "BB_{}:", step_label)?;
+                        self.generate(step, false)?;
+                    }
 
                     // jump to entry block
-                    write!(self.buffer, "    jmp BB_{}\n", start_label)?;
+                    writeln!(self.buffer, "    jmp BB_{}", start_label)?;
 
                     // exit block
-                    write!(self.buffer, "BB_{}:\n", exit_label)?;
-                    self.block_count += 2;
+                    writeln!(self.buffer, "BB_{}:", exit_label)?;
+                },
+                Ast::Match {value, arms, default, ..} => {
+                    self.buffer.write_all(b"    ;; -- MATCH --\n")?;
+                    let val_type = self.value_type(value);
+                    let value = self.value(value)?;
+                    let val_reg = self.val_is_in_reg(value)?;
+                    if val_reg != "rax" {
+                        writeln!(self.buffer, "    mov rax, {}", val_reg)?;
+                    }
+
+                    let exit_label = self.block();
+                    let default_label = self.block();
+                    let mut arm_labels: Vec<usize> = Vec::new();
+                    let mut cases: Vec<(i128, usize)> = Vec::new();
+                    for (patterns, _) in arms.iter() {
+                        let arm_label = self.block();
+                        for pattern in patterns {
+                            let case = fold::truncate(fold::evaluate(pattern).expect("internal compiler error"), &val_type);
+                            // signed cases are ordered by their signed value
+                            let case = if val_type.is_signed() { case as i64 as i128 } else { case as i128 };
+                            cases.push((case, arm_label));
+                        }
+                        arm_labels.push(arm_label);
+                    }
+                    cases.sort();
+
+                    let (min, max) = (cases.first().map_or(0, |case| case.0), cases.last().map_or(0, |case| case.0));
+                    if cases.len() >= 4 && max - min < cases.len() as i128 * 2 {
+                        // dense cases jump through a table indexed by the value minus the smallest case
+                        let table_label = self.block();
+                        let mut table: Vec<String> = Vec::new();
+                        for case in min..=max {
+                            let label = cases.iter().find(|arm| arm.0 == case).map_or(default_label, |arm| arm.1);
+                            table.push(format!("BB_{}", label));
+                        }
+                        self.static_data.push(format!("table_{} dq {}", table_label, table.join(", ")));
+
+                        writeln!(self.buffer, "    mov rbx, {}", min as i64)?;
+                        self.buffer.write_all(b"    sub rax, rbx\n")?;
+                        writeln!(self.buffer, "    cmp rax, {}", max - min)?;
+                        // values below the smallest case wrap around and are bigger than the table as well
+                        writeln!(self.buffer, "    ja BB_{}", default_label)?;
+                        writeln!(self.buffer, "    lea rbx, [table_{}]", table_label)?;
+                        self.buffer.write_all(b"    jmp qword [rbx+rax*8]\n")?;
+                    } else {
+                        // sparse cases are compared one by one
+                        for (case, label) in cases.iter() {
+                            writeln!(self.buffer, "    mov rbx, {}", *case as i64)?;
+                            self.buffer.write_all(b"    cmp rax, rbx\n")?;
+                            writeln!(self.buffer, "    je BB_{}", label)?;
+                        }
+                        writeln!(self.buffer, "    jmp BB_{}", default_label)?;
+                    }
+
+                    for ((_, body), arm_label) in arms.iter().zip(arm_labels) {
+                        writeln!(self.buffer, "BB_{}:", arm_label)?;
+                        self.generate(body, false)?;
+                        writeln!(self.buffer, "    jmp BB_{}", exit_label)?;
+                    }
+                    writeln!(self.buffer, "BB_{}:", default_label)?;
+                    if let Some(default) = default {
+                        self.generate(default, false)?;
+                    }
+                    writeln!(self.buffer, "BB_{}:", exit_label)?;
+                },
+                Ast::Block {body, ..} => {
+                    self.generate(body, false)?;
+                },
+                Ast::Break {label, ..} | Ast::Continue {label, ..} => {
+                    // without a label the innermost loop is used
+                    let target = self.loops.iter().rev()
+                        .find(|target| label.is_none() || target.0 == *label)
+                        .expect("internal compiler error");
+                    if let Ast::Break {..} = instruction {
+                        self.buffer.write_all(b"    ;; -- BREAK --\n")?;
+                        writeln!(self.buffer, "    jmp BB_{}", target.2)?;
+                    } else {
+                        self.buffer.write_all(b"    ;; -- CONTINUE --\n")?;
+                        writeln!(self.buffer, "    jmp BB_{}", target.1)?;
+                    }
                 },
                 Ast::InlineAsm {asm, ..} => {
-                    write!(self.buffer, "{}\n", asm)?;
+                    writeln!(self.buffer, "{}", asm)?;
+                },
+                Ast::Include {..} | Ast::Import {..} | Ast::Pub {..} => {},
+                Ast::Expr {value, ..} => {
+                    self.buffer.write_all(b"    ;; -- EXPRESSION --\n")?;
+                    let old_stack_offset = self.stack_offset;
+
+                    // the result is left in a register and thrown away
+                    self.value(value)?;
+
+                    self.stack_offset = old_stack_offset;
                 },
             }
         }
 
         // drop variables created in the current scope
         for var in &local_vars {
-            let size = self.variables.remove(var).expect("internal compiler error");
-            self.stack_offset -= size.1;
+            let var = self.variables.remove(var).expect("internal compiler error");
+            self.stack_offset -= stack_size(&var.1);
         }
 
         if entry {
             self.exit()?;
         }
-        return Ok(());
+        Ok(())
     }
 
     pub fn flush(&mut self) -> Result<(), Box<dyn std::error::Error>> {
         self.buffer.flush()?;
-        return Ok(());
+        Ok(())
     }
 
     pub fn assemble(&self) -> Result<String, Box<dyn std::error::Error>> {
@@ -341,13 +926,13 @@ impl CodeGen {
             .arg("+x")
             .arg(filename)
             .output()?;
-        return Ok(String::from_utf8(output.stdout)?);
+        Ok(String::from_utf8(output.stdout)?)
     }
 
     pub fn run(&self) -> Result<(), Box<dyn std::error::Error>> {
         let filename = get_filename(&self.filename)?;
-        let _ = Command::new(&format!("./{}", filename)).spawn()?;
-        return Ok(());
+        let _ = Command::new(format!("./{}", filename)).spawn()?;
+        Ok(())
     }
 }
 
@@ -1,138 +1,474 @@
 use lib_lexin::Token;
-use crate::{log_color, generate_ast};
-
+use crate::diagnostic::{Diagnostic, Diagnostics};
+use crate::source::Loc;
+
+const TYPES: [&str; 12] = [
+    "int",
+    "i8",
+    "i16",
+    "i32",
+    "i64",
+    "u8",
+    "u16",
+    "u32",
+    "u64",
+    "ptr",
+    "bool",
+    "void",
+];
+
+
+// init, condition and step of a for loop
+type LoopHeader = (Vec<Ast>, Value, Vec<Ast>);
+
+// patterns and bodies of the arms of a match, the body of the `_` arm is kept apart
+type MatchArms = (Vec<(Vec<Value>, Vec<Ast>)>, Option<Vec<Ast>>);
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Type {
     Int,
+    I8,
+    I16,
+    I32,
+    I64,
+    U8,
+    U16,
+    U32,
+    U64,
+    Bool,
     Ptr,
+    // pointer to a value of the type, `ptr` is a pointer to anything
+    TypedPtr(Box<Type>),
+    Struct(Struct),
+    Enum(Enum),
+    // element type, length
+    Array(Box<Type>, usize),
+    // array with the length given by a constant and where the constant is written, resolved into `Array`
+    ConstArray(Box<Type>, String, Loc),
     Void,
 }
 
+#[derive(Clone)]
+pub struct Struct {
+    pub name: String,
+    // fields are empty until the struct is resolved
+    pub fields: Vec<(String, Type)>,
+    // where the name is written, types are compared without it
+    pub loc: Loc,
+}
+
+impl PartialEq for Struct {
+    fn eq(&self, other: &Self) -> bool {
+        self.name == other.name && self.fields == other.fields
+    }
+}
+
+impl Eq for Struct {}
+
+impl std::fmt::Debug for Struct {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name)
+    }
+}
+
+impl Struct {
+    // offsets of the fields, every field is aligned to its own alignment
+    pub fn offsets(&self) -> Vec<usize> {
+        let mut offsets: Vec<usize> = Vec::new();
+        let mut offset: usize = 0;
+        for (_, field_t) in &self.fields {
+            offset = offset.next_multiple_of(field_t.align());
+            offsets.push(offset);
+            offset += field_t.size();
+        }
+        offsets
+    }
+
+    pub fn field(&self, name: &str) -> Option<(usize, Type)> {
+        let offsets = self.offsets();
+        for (index, (field_name, field_t)) in self.fields.iter().enumerate() {
+            if field_name == name {
+                return Some((offsets[index], field_t.clone()));
+            }
+        }
+        None
+    }
+}
+
+#[derive(Clone, PartialEq, Eq)]
+pub struct Enum {
+    pub name: String,
+    // variants with their discriminant
+    pub variants: Vec<(String, usize)>,
+}
+
+impl std::fmt::Debug for Enum {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name)
+    }
+}
+
+impl Enum {
+    pub fn variant(&self, name: &str) -> Option<usize> {
+        self.variants.iter().find(|variant| variant.0 == name).map(|variant| variant.1)
+    }
+}
+
 impl Type {
     pub fn size(&self) -> usize {
-        return match self {
-            Type::Int | Type::Ptr => 8,
-            Type::Void => 0,
-        };
+        match self {
+            Type::I8 | Type::U8 | Type::Bool => 1,
+            Type::I16 | Type::U16 => 2,
+            Type::I32 | Type::U32 => 4,
+            Type::Int | Type::I64 | Type::U64 | Type::Ptr | Type::TypedPtr(_) | Type::Enum(_) => 8,
+            Type::Struct(struct_t) => {
+                let end = match struct_t.fields.last() {
+                    Some((_, field_t)) => struct_t.offsets()[struct_t.fields.len() - 1] + field_t.size(),
+                    None => 0,
+                };
+                end.next_multiple_of(self.align())
+            },
+            Type::Array(elem_t, len) => elem_t.size() * len,
+            Type::ConstArray(..) | Type::Void => 0,
+        }
     }
-}
 
-#[derive(Debug)]
-pub struct Comparison {
-    pub l_expr: Box<Value>,
-    pub r_expr: Box<Value>,
-    pub op: ComparisonOp,
+    pub fn align(&self) -> usize {
+        match self {
+            Type::Struct(struct_t) => struct_t.fields.iter().map(|field| field.1.align()).max().unwrap_or(1),
+            Type::Array(elem_t, _) => elem_t.align(),
+            _ => self.size().max(1),
+        }
+    }
+
+    // structs and arrays live in memory and cant be moved through a register
+    pub fn is_aggregate(&self) -> bool {
+        matches!(self, Type::Struct(_) | Type::Array(..))
+    }
+
+    pub fn is_signed(&self) -> bool {
+        matches!(self, Type::I8 | Type::I16 | Type::I32 | Type::I64)
+    }
+
+    pub fn is_integer(&self) -> bool {
+        self.is_signed() || matches!(self, Type::Int | Type::U8 | Type::U16 | Type::U32 | Type::U64)
+    }
+
+    pub fn is_pointer(&self) -> bool {
+        matches!(self, Type::Ptr | Type::TypedPtr(_))
+    }
+
+    // integers are widened without a cast when no value can be lost, typed pointers convert to `ptr`
+    pub fn widens_to(&self, other: &Type) -> bool {
+        if self == other || (matches!(self, Type::TypedPtr(_)) && *other == Type::Ptr) {
+            return true;
+        } else if !self.is_integer() || !other.is_integer() || (self.is_signed() && !other.is_signed()) {
+            return false;
+        }
+        self.size() < other.size()
+    }
+
+    // the type two values are converted to when they are used together
+    pub fn common(&self, other: &Type) -> Option<Type> {
+        if self.widens_to(other) {
+            return Some(other.clone());
+        } else if other.widens_to(self) {
+            return Some(self.clone());
+        }
+        None
+    }
 }
 
-#[derive(Debug, Eq, PartialEq)]
+#[derive(Debug, Clone, Eq, PartialEq)]
 pub enum ComparisonOp {
     Equal,
     NotEqual,
     Bigger,
     Smaller,
+    BiggerEqual,
+    SmallerEqual,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum LogicalOp {
+    And,
+    Or,
 }
 
-#[derive(Debug, Eq, PartialEq)]
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum UnaryOp {
+    Neg,
+    Not,
+    BitNot,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
 pub enum Operator {
     Plus,
     Minus,
     Multiplication,
     Divide,
+    Modulo,
+    BitAnd,
+    BitOr,
+    BitXor,
+    ShiftLeft,
+    ShiftRight,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum Value {
     BinaryExpr {
-        loc: (usize, usize),
+        loc: Loc,
         l_expr: Box<Value>,
         r_expr: Box<Value>,
         op: Operator,
     },
+    Comparison {
+        loc: Loc,
+        l_expr: Box<Value>,
+        r_expr: Box<Value>,
+        op: ComparisonOp,
+    },
+    Logical {
+        loc: Loc,
+        l_expr: Box<Value>,
+        r_expr: Box<Value>,
+        op: LogicalOp,
+    },
+    Unary {
+        loc: Loc,
+        value: Box<Value>,
+        op: UnaryOp,
+    },
     FunctionCall {
-        loc: (usize, usize),
+        loc: Loc,
         name: String,
         params: Vec<Value>,
     },
-    Cast(Box<Value>, Type),
-    Deref(Box<Value>, Type),
-    Ref(Box<Value>),
+    Field {
+        loc: Loc,
+        value: Box<Value>,
+        field: String,
+    },
+    // `Name[ptr]` is parsed as an index and turned into a dereference if `Name` is a struct
+    Index {
+        loc: Loc,
+        value: Box<Value>,
+        index: Box<Value>,
+    },
+    // `Name::Variant`, the type is resolved into the enum
+    Variant {
+        loc: Loc,
+        enum_t: Type,
+        variant: String,
+    },
+    // `*ptr`, the width comes from the type of the pointer
+    Load {
+        loc: Loc,
+        value: Box<Value>,
+    },
+    // `type(value)`
+    Cast {
+        loc: Loc,
+        value: Box<Value>,
+        cast_t: Type,
+    },
+    // `type[ptr]`
+    Deref {
+        loc: Loc,
+        value: Box<Value>,
+        deref_t: Type,
+    },
+    Ref {
+        loc: Loc,
+        value: Box<Value>,
+    },
+    Sizeof {
+        loc: Loc,
+        size_t: Type,
+    },
     Int(usize),
+    Bool(bool),
     Str(String),
-    Ident(String),
+    Ident {
+        loc: Loc,
+        name: String,
+    },
     Null,
 }
 
+impl Value {
+    // integer literals take the type of the integer they are used with
+    pub fn is_literal(&self) -> bool {
+        match self {
+            Value::Int(_) | Value::Sizeof {..} => true,
+            Value::BinaryExpr {l_expr, r_expr, ..} => l_expr.is_literal() && r_expr.is_literal(),
+            Value::Unary {value, op: UnaryOp::Neg | UnaryOp::BitNot, ..} => value.is_literal(),
+            _ => false,
+        }
+    }
+}
+
 #[derive(Debug)]
 pub enum Ast {
     Function {
-        loc: (usize, usize),
+        loc: Loc,
         name: String,
         param_t: Vec<(String, Type)>,
+        // name of the pointer to the extra arguments of a variadic function
+        variadic: Option<String>,
         return_t: Type,
         body: Vec<Ast>,
     },
 
     Return {
-        loc: (usize, usize),
+        loc: Loc,
         value: Value,
     },
 
     Variable {
-        loc: (usize, usize),
+        loc: Loc,
         name: String,
         var_t: Type,
         value: Value,
     },
 
     MutateVar {
-        loc: (usize, usize),
+        loc: Loc,
         name: String,
         value: Value,
     },
 
     MutatePtr {
-        loc: (usize, usize),
+        loc: Loc,
         ptr_type: Type,
         ptr: Value,
         value: Value,
     },
 
     If {
-        loc: (usize, usize),
-        comparison: Comparison,
+        loc: Loc,
+        condition: Value,
         body: Vec<Ast>,
         else_body: Vec<Ast>,
     },
 
+    // `step` runs after the body and on continue, for loops are lowered into a block with a while loop
     While {
-        loc: (usize, usize),
-        comparison: Comparison,
+        loc: Loc,
+        label: Option<String>,
+        condition: Value,
+        body: Vec<Ast>,
+        step: Vec<Ast>,
+    },
+
+    // `default` is the body of the `_` arm
+    Match {
+        loc: Loc,
+        value: Value,
+        arms: Vec<(Vec<Value>, Vec<Ast>)>,
+        default: Option<Vec<Ast>>,
+    },
+
+    // statements with their own scope
+    Block {
+        #[allow(dead_code)]
+        loc: Loc,
         body: Vec<Ast>,
     },
 
+    Break {
+        loc: Loc,
+        label: Option<String>,
+    },
+
+    Continue {
+        loc: Loc,
+        label: Option<String>,
+    },
+
     InlineAsm {
-        loc: (usize, usize),
+        #[allow(dead_code)]
+        loc: Loc,
         asm: String,
     },
+
+    // `include "path"`, replaced by the items of the file when the module is loaded
+    Include {
+        loc: Loc,
+        path: String,
+    },
+
+    // `import a.b;` loads `a/b.lang` as the module `b`
+    Import {
+        loc: Loc,
+        path: Vec<String>,
+    },
+
+    // `pub` makes the next declaration visible to the modules importing it
+    Pub {
+        loc: Loc,
+    },
+
+    Expr {
+        #[allow(dead_code)]
+        loc: Loc,
+        value: Value,
+    },
+
+    Struct {
+        loc: Loc,
+        name: String,
+        fields: Vec<(String, Type)>,
+    },
+
+    Const {
+        loc: Loc,
+        name: String,
+        const_t: Type,
+        value: Value,
+    },
+
+    // variants without a value are one bigger than the previous variant
+    Enum {
+        loc: Loc,
+        name: String,
+        variants: Vec<(String, Option<Value>)>,
+    },
+
+    // assignment to a field or any other target that isnt a variable or a dereference
+    Assign {
+        loc: Loc,
+        target: Value,
+        value: Value,
+    },
+
+    // `target op= value`, `++` and `--` add or subtract 1
+    Compound {
+        loc: Loc,
+        target: Value,
+        op: Operator,
+        value: Value,
+    },
 }
 
 
 impl Ast {
-    fn bound_check(tokens: &Vec<Token>, index: &mut usize, expected: &str) -> Result<(), Box<dyn std::error::Error>> {
-        let loc = if tokens.len() != 0 {
-            tokens[tokens.len() - 1].loc()
+    fn bound_check(tokens: &[Token], index: &mut usize, expected: &str) -> Result<(), Box<dyn std::error::Error>> {
+        let loc = if !tokens.is_empty() {
+            Loc::of(&tokens[tokens.len() - 1])
         } else {
-            (0, 0)
+            Loc::default()
         };
         *index += 1;
         if *index >= tokens.len() {
-            return Err(format!("{} expected `{}`", log_color(loc), expected).into());
+            return Err(Diagnostic::error(loc, format!("expected `{}`", expected)).into());
         }
-        return Ok(());
+        Ok(())
     }
 
-    fn is_operator(token: &Token, loc: (usize, usize)) -> Result<Operator, Box<dyn std::error::Error>> {
+    fn is_operator(token: &Token, loc: Loc) -> Result<Operator, Box<dyn std::error::Error>> {
         return if token.is_symbol("Plus").is_ok() {
             Ok(Operator::Plus)
         } else if token.is_symbol("Minus").is_ok() {
@@ -141,182 +477,365 @@ impl Ast {
             Ok(Operator::Multiplication)
         } else if token.is_symbol("Slash").is_ok() {
             Ok(Operator::Divide)
+        } else if token.is_symbol("Percent").is_ok() {
+            Ok(Operator::Modulo)
+        } else if token.is_symbol("And").is_ok() {
+            // a `&` after a value is a bitwise and, before a value it is a reference
+            Ok(Operator::BitAnd)
+        } else if token.is_symbol("Pipe").is_ok() {
+            Ok(Operator::BitOr)
+        } else if token.is_symbol("Caret").is_ok() {
+            Ok(Operator::BitXor)
+        } else if token.is_symbol("ShiftLeft").is_ok() {
+            Ok(Operator::ShiftLeft)
+        } else if token.is_symbol("ShiftRight").is_ok() {
+            Ok(Operator::ShiftRight)
         } else {
-            Err(format!("{} expected `operator`", log_color(loc)).into())
+            Err(Diagnostic::error(loc, "expected `operator`").into())
         }
     }
 
-    fn single_expr(tokens: &Vec<Token>, loc: (usize, usize)) -> Result<Value, Box<dyn std::error::Error>> {
-        let mut index = 0;
-        if index >= tokens.len() {
-            return Err(format!("{} empty expression", log_color(loc)).into());
+    fn precedence(op: &Operator) -> usize {
+        match op {
+            Operator::Multiplication | Operator::Divide | Operator::Modulo => 6,
+            Operator::Plus | Operator::Minus => 5,
+            Operator::ShiftLeft | Operator::ShiftRight => 4,
+            Operator::BitAnd => 3,
+            Operator::BitXor => 2,
+            Operator::BitOr => 1,
+        }
+    }
+
+    fn is_compound(token: &Token) -> Option<Operator> {
+        let compound = [
+            ("PlusEqual", Operator::Plus),
+            ("MinusEqual", Operator::Minus),
+            ("AsteriskEqual", Operator::Multiplication),
+            ("SlashEqual", Operator::Divide),
+            ("PercentEqual", Operator::Modulo),
+            ("AndEqual", Operator::BitAnd),
+            ("PipeEqual", Operator::BitOr),
+            ("CaretEqual", Operator::BitXor),
+            ("ShiftLeftEqual", Operator::ShiftLeft),
+            ("ShiftRightEqual", Operator::ShiftRight),
+        ];
+        compound.into_iter().find(|symbol| token.is_symbol(symbol.0).is_ok()).map(|symbol| symbol.1)
+    }
+
+    fn single_expr(tokens: &Vec<Token>, index: &mut usize, loc: Loc) -> Result<Value, Box<dyn std::error::Error>> {
+        if *index >= tokens.len() {
+            return Err(Diagnostic::error(loc, "expected `value`").into());
         }
 
-        if let Ok(integer) = tokens[index].is_integer() {
+        if let Ok(integer) = tokens[*index].is_integer() {
+            *index += 1;
             return Ok(Value::Int(integer));
-        } else if let Ok(ident) = tokens[index].is_ident() {
-            return Ok(Value::Ident(ident));
-        } else if let Ok(string) = tokens[index].is_section("string") {
+        } else if tokens[*index].is_keyword("true").is_ok() {
+            *index += 1;
+            return Ok(Value::Bool(true));
+        } else if tokens[*index].is_keyword("false").is_ok() {
+            *index += 1;
+            return Ok(Value::Bool(false));
+        } else if let Ok(string) = tokens[*index].is_section("string") {
+            *index += 1;
             return Ok(Value::Str(string));
-        } else if let Ok(deref_type) = Self::is_type(tokens[index].clone()) {
+        } else if tokens[*index].is_ident().is_ok() {
+            let (mut path, loc) = Self::path(tokens, index)?;
+            let is_call = *index < tokens.len() && tokens[*index].is_symbol("OpenParen").is_ok();
+            if path.len() > 1 && !is_call {
+                // ENUM VARIANT, the loader turns `module::name` into a identifier
+                let variant = path.pop().expect("internal compiler error");
+
+                return Ok(Value::Variant {
+                    loc,
+                    enum_t: Type::Struct(Struct {
+                        name: path.join("::"),
+                        fields: Vec::new(),
+                        // the path without `::variant`
+                        loc: loc.to(Loc::of(&tokens[*index - 4])),
+                    }),
+                    variant,
+                });
+            }
+            let name = path.join("::");
+            if !is_call {
+                return Ok(Value::Ident {
+                    loc,
+                    name,
+                });
+            }
+
+            // FUNCTION CALL
+            Self::bound_check(tokens, index, "CloseParen")?;
+            let params = Self::scope(tokens, index, "OpenParen", "CloseParen")?;
+            *index += 1;
+
+            return Ok(Value::FunctionCall {
+                loc,
+                name,
+                params: Self::parse_call_params(&params)?,
+            });
+        } else if tokens[*index].is_keyword("sizeof").is_ok() {
+            // SIZEOF
+            let loc = Loc::of(&tokens[*index]);
+            Self::bound_check(tokens, index, "OpenParen")?;
+            if tokens[*index].is_symbol("OpenParen").is_err() {
+                return Err(Diagnostic::error(loc, "expected `(` in sizeof").into());
+            }
+            Self::bound_check(tokens, index, "type")?;
+            let size_t = Self::parse_type(tokens, index)?;
+            if *index >= tokens.len() || tokens[*index].is_symbol("CloseParen").is_err() {
+                return Err(Diagnostic::error(loc, "expected `)` in sizeof").into());
+            }
+            *index += 1;
+
+            return Ok(Value::Sizeof {
+                loc,
+                size_t,
+            });
+        } else if tokens[*index].is_symbol("OpenParen").is_ok() {
+            // PARENTHESES
+            let loc = Loc::of(&tokens[*index]);
+            Self::bound_check(tokens, index, "CloseParen")?;
+            let value = Self::scope(tokens, index, "OpenParen", "CloseParen")?;
+            *index += 1;
+
+            return Self::expr(&value, loc);
+        } else if Self::is_type(tokens[*index].clone()).is_ok() {
             // DEREFERENCE
-            let loc = tokens[index].loc();
+            let loc = Loc::of(&tokens[*index]);
 
-            Self::bound_check(tokens, &mut index, "OpenBracket")?;
+            let deref_type = Self::parse_type(tokens, index)?;
+            if *index >= tokens.len() {
+                return Err(Diagnostic::error(loc, "expected `OpenBracket`").into());
+            }
 
             let dereference: bool;
-            let value = if tokens[index].is_symbol("OpenBracket").is_ok() {
+            let value = if tokens[*index].is_symbol("OpenBracket").is_ok() {
                 dereference = true;
-                Self::bound_check(tokens, &mut index, "CloseBracket")?;
-                Self::scope(tokens, &mut index, "OpenBracket", "CloseBracket")?
-            } else if tokens[index].is_symbol("OpenParen").is_ok() {
+                Self::bound_check(tokens, index, "CloseBracket")?;
+                Self::scope(tokens, index, "OpenBracket", "CloseBracket")?
+            } else if tokens[*index].is_symbol("OpenParen").is_ok() {
                 dereference = false;
-                Self::bound_check(tokens, &mut index, "CloseParen")?;
-                Self::scope(tokens, &mut index, "OpenParen", "CloseParen")?
+                Self::bound_check(tokens, index, "CloseParen")?;
+                Self::scope(tokens, index, "OpenParen", "CloseParen")?
             } else {
-                return Err(format!("{} expected `OpenBracket`", log_color(loc)).into());
+                return Err(Diagnostic::error(loc, "expected `OpenBracket`").into());
             };
-
-            // Self::bound_check(tokens, &mut index, seperator_name)?;
-
-            //let mut value: Vec<Token> = Vec::new();
-            //while tokens[index].is_symbol(seperator_name).is_err() {
-            //    value.push(tokens[index].clone());
-            //    Self::bound_check(tokens, &mut index, seperator_name)?;
-            //}
+            *index += 1;
 
             if dereference {
-                return Ok(Value::Deref(Box::new(Self::expr(&value, loc)?), Self::str_to_type(deref_type)));
+                return Ok(Value::Deref {
+                    loc,
+                    value: Box::new(Self::expr(&value, loc)?),
+                    deref_t: deref_type,
+                });
             } else { // CAST
-                return Ok(Value::Cast(Box::new(Self::expr(&value, loc)?), Self::str_to_type(deref_type)));
+                return Ok(Value::Cast {
+                    loc,
+                    value: Box::new(Self::expr(&value, loc)?),
+                    cast_t: deref_type,
+                });
             }
+        } else if tokens[*index].is_symbol("Asterisk").is_ok() {
+            // POINTER DEREFERENCE
+            let loc = Loc::of(&tokens[*index]);
+            *index += 1;
 
-        } else if tokens[index].is_symbol("And").is_ok() {
+            return Ok(Value::Load {
+                loc,
+                value: Box::new(Self::postfix_expr(tokens, index, loc)?),
+            });
+        } else if tokens[*index].is_symbol("And").is_ok() {
             // REFERENCE
-            let loc = tokens[index].loc();
+            let loc = Loc::of(&tokens[*index]);
+            *index += 1;
 
-            Self::bound_check(tokens, &mut index, "Value")?;
+            return Ok(Value::Ref {
+                loc,
+                value: Box::new(Self::postfix_expr(tokens, index, loc)?),
+            });
+        } else if tokens[*index].is_symbol("Bang").is_ok() {
+            // NOT
+            let loc = Loc::of(&tokens[*index]);
+            *index += 1;
 
-            let mut value: Vec<Token> = Vec::new();
-            while index < tokens.len() {
-                value.push(tokens[index].clone());
-                index += 1; // not accident
-            }
+            return Ok(Value::Unary {
+                loc,
+                value: Box::new(Self::postfix_expr(tokens, index, loc)?),
+                op: UnaryOp::Not,
+            });
+        } else if tokens[*index].is_symbol("Minus").is_ok() {
+            // NEGATION
+            let loc = Loc::of(&tokens[*index]);
+            *index += 1;
 
-            return Ok(Value::Ref(Box::new(Self::expr(&value, loc)?)));
+            return Ok(Value::Unary {
+                loc,
+                value: Box::new(Self::postfix_expr(tokens, index, loc)?),
+                op: UnaryOp::Neg,
+            });
+        } else if tokens[*index].is_symbol("Tilde").is_ok() {
+            // BITWISE NOT
+            let loc = Loc::of(&tokens[*index]);
+            *index += 1;
+
+            return Ok(Value::Unary {
+                loc,
+                value: Box::new(Self::postfix_expr(tokens, index, loc)?),
+                op: UnaryOp::BitNot,
+            });
         }
-        return Ok(Value::Null);
+        let loc = Loc::of(&tokens[*index]);
+        Err(Diagnostic::error(loc, format!("expected `value` but got `{}`", Loc::of(&tokens[*index]).slice())).into())
     }
 
-    fn is_binary_expr(tokens: &Vec<Token>) -> bool {
-        let mut scope_c = 0;
-        let mut index = 0;
-        while index < tokens.len() {
-            if tokens[index].is_symbol("OpenParen").is_ok() || tokens[index].is_symbol("OpenBracket").is_ok() {
-                scope_c += 1;
-            } else if tokens[index].is_symbol("CloseParen").is_ok() || tokens[index].is_symbol("CloseBracket").is_ok() {
-                scope_c -= 1;
-            }
-            if Self::is_operator(&tokens[index].clone(), tokens[index].loc()).is_ok() && scope_c == 0 {
-                return true;
+    // field access and indexing bind tighter than any prefix operator
+    fn postfix_expr(tokens: &Vec<Token>, index: &mut usize, loc: Loc) -> Result<Value, Box<dyn std::error::Error>> {
+        let mut value = Self::single_expr(tokens, index, loc)?;
+
+        while *index < tokens.len() {
+            let loc = Loc::of(&tokens[*index]);
+            if tokens[*index].is_symbol("OpenBracket").is_ok() {
+                Self::bound_check(tokens, index, "CloseBracket")?;
+                let index_value = Self::scope(tokens, index, "OpenBracket", "CloseBracket")?;
+                *index += 1;
+
+                value = Value::Index {
+                    loc,
+                    value: Box::new(value),
+                    index: Box::new(Self::expr(&index_value, loc)?),
+                };
+                continue;
+            } else if tokens[*index].is_symbol("Dot").is_err() {
+                break;
             }
-            index += 1; // intentional
-        }
-        return false;
-    }
 
-    fn is_function_call(tokens: &Vec<Token>) -> bool {
-        if tokens.len() < 2 {
-            return false;
-        } else if tokens[0].is_ident().is_ok() && tokens[1].is_symbol("OpenParen").is_ok() {
-            return true;
+            Self::bound_check(tokens, index, "ident")?;
+            let field = match tokens[*index].is_ident() {
+                Ok(field) => field,
+                Err(_) => {
+                    let loc = Loc::of(&tokens[*index]);
+                    return Err(Diagnostic::error(loc, format!("expected `ident` but got `{}`", Loc::of(&tokens[*index]).slice())).into());
+                },
+            };
+            *index += 1;
+
+            value = Value::Field {
+                loc,
+                value: Box::new(value),
+                field,
+            };
         }
-        return false;
+
+        Ok(value)
     }
 
-    fn expr(tokens: &Vec<Token>, loc: (usize, usize)) -> Result<Value, Box<dyn std::error::Error>> {
-        let mut index = 0;
-        if index >= tokens.len() {
-            return Err(format!("{} empty expression", log_color(loc)).into());
-        }
+    // precedence climbing, operators of equal precedence are left associative
+    fn binary_expr(tokens: &Vec<Token>, index: &mut usize, min_precedence: usize, loc: Loc) -> Result<Value, Box<dyn std::error::Error>> {
+        let mut l_expr = Self::postfix_expr(tokens, index, loc)?;
 
-       if Self::is_binary_expr(tokens) {
-            // l_expr [operator] r_expr
-            let loc = tokens[index].loc();
-            let mut l_expr: Vec<Token> = Vec::new();
-            let l_loc = tokens[index].loc();
-            while Self::is_operator(&tokens[index], tokens[index].loc()).is_err() {
-                l_expr.push(tokens[index].clone());
-                Self::bound_check(tokens, &mut index, "operator")?;
+        while *index < tokens.len() {
+            let op = match Self::is_operator(&tokens[*index], Loc::of(&tokens[*index])) {
+                Ok(op) => op,
+                Err(_) => break,
+            };
+            let precedence = Self::precedence(&op);
+            if precedence < min_precedence {
+                break;
             }
+            let loc = Loc::of(&tokens[*index]);
+            *index += 1;
 
-            let op = Self::is_operator(&tokens[index], tokens[index].loc())?;
+            let r_expr = Self::binary_expr(tokens, index, precedence + 1, loc)?;
+            l_expr = Value::BinaryExpr {
+                loc,
+                l_expr: Box::new(l_expr),
+                r_expr: Box::new(r_expr),
+                op,
+            };
+        }
 
-            Self::bound_check(tokens, &mut index, "SemiColon")?;
+        Ok(l_expr)
+    }
 
-            let mut r_expr: Vec<Token> = Vec::new();
-            let r_loc = tokens[index].loc();
-            while index < tokens.len() { // this will always break out if its out of bounds
-                r_expr.push(tokens[index].clone());
-                index += 1; // intentional because if we bound check here it will always error
-                            // because we iterate to the end of the vector
-            }
+    // comparisons dont chain, `a < b < c` is an error
+    fn comparison_expr(tokens: &Vec<Token>, index: &mut usize, loc: Loc) -> Result<Value, Box<dyn std::error::Error>> {
+        let l_expr = Self::binary_expr(tokens, index, 0, loc)?;
+
+        if let Ok(op) = Self::is_comparison_op(tokens, *index) {
+            let loc = Loc::of(&tokens[*index]);
+            *index += 1;
 
-            return Ok(Value::BinaryExpr {
+            let r_expr = Self::binary_expr(tokens, index, 0, loc)?;
+            return Ok(Value::Comparison {
                 loc,
-                l_expr: Box::new(Self::expr(&l_expr, l_loc)?),
-                r_expr: Box::new(Self::expr(&r_expr, r_loc)?),
+                l_expr: Box::new(l_expr),
+                r_expr: Box::new(r_expr),
                 op,
             });
-        } else if Self::is_function_call(tokens) {
-            // FUNCTION CALL
-            let loc = tokens[index].loc();
-            let name = tokens[index].is_ident()?;
+        }
 
-            Self::bound_check(tokens, &mut index, "OpenParen")?;
-            if tokens[index].is_symbol("OpenParen").is_err() {
-                return Err(format!("{} expected `(` in function call", log_color(loc)).into());
-            }
+        Ok(l_expr)
+    }
 
-            Self::bound_check(tokens, &mut index, "CloseParen")?;
+    fn logical_expr(tokens: &Vec<Token>, index: &mut usize, min_precedence: usize, loc: Loc) -> Result<Value, Box<dyn std::error::Error>> {
+        let mut l_expr = Self::comparison_expr(tokens, index, loc)?;
 
-            //let mut params: Vec<Token> = Vec::new();
-            //while tokens[index].is_symbol("CloseParen").is_err() {
-            //    params.push(tokens[index].clone());
-            //    Self::bound_check(tokens, &mut index, "CloseParen")?;
-            //}
-            let params = Self::scope(tokens, &mut index, "OpenParen", "CloseParen")?;
+        while let Ok(op) = Self::is_logical_op(tokens, *index) {
+            // && binds tighter than ||
+            let precedence = match op {
+                LogicalOp::And => 2,
+                LogicalOp::Or => 1,
+            };
+            if precedence < min_precedence {
+                break;
+            }
+            let loc = Loc::of(&tokens[*index]);
+            *index += 1;
 
-            return Ok(Value::FunctionCall {
+            let r_expr = Self::logical_expr(tokens, index, precedence + 1, loc)?;
+            l_expr = Value::Logical {
                 loc,
-                name,
-                params: Self::parse_call_params(&params)?,
-            });
-        } else {
-            // SINGLE EXPR
-            return Self::single_expr(tokens, loc);
+                l_expr: Box::new(l_expr),
+                r_expr: Box::new(r_expr),
+                op,
+            };
         }
+
+        Ok(l_expr)
+    }
+
+    fn expr(tokens: &Vec<Token>, loc: Loc) -> Result<Value, Box<dyn std::error::Error>> {
+        let mut index = 0;
+        if index >= tokens.len() {
+            return Err(Diagnostic::error(loc, "empty expression").into());
+        }
+
+        let value = Self::logical_expr(tokens, &mut index, 0, loc)?;
+        if index < tokens.len() {
+            let loc = Loc::of(&tokens[index]);
+            return Err(Diagnostic::error(loc, format!("unexpected `{}` in expression", loc.slice())).into());
+        }
+        Ok(value)
     }
 
     fn parse_call_params(tokens: &Vec<Token>) -> Result<Vec<Value>, Box<dyn std::error::Error>> {
         let mut index = 0;
         let mut params: Vec<Value> = Vec::new();
-        let mut param: Vec<Token> = Vec::new();
 
         while index < tokens.len() {
-            // NOTE: ADD SUPPORT FOR NESTING CALLS WITH MULTIPLE ARGS
-            if tokens[index].is_symbol("Comma").is_ok() {
-                params.push(Self::expr(&param, tokens[index - param.len()].loc())?);
-                param = Vec::new();
-            } else {
-                param.push(tokens[index].clone());
+            let param_loc = Loc::of(&tokens[index]);
+            params.push(Self::logical_expr(tokens, &mut index, 0, param_loc)?);
+            if index < tokens.len() {
+                if tokens[index].is_symbol("Comma").is_err() {
+                    let loc = Loc::of(&tokens[index]);
+                    return Err(Diagnostic::error(loc, format!("expected `Comma` but got `{}`", Loc::of(&tokens[index]).slice())).into());
+                }
+                Self::bound_check(tokens, &mut index, "Value")?;
             }
-            index += 1; // safe
-        }
-        if param.len() != 0 {
-            params.push(Self::expr(&param, tokens[index - param.len()].loc())?);
         }
-        return Ok(params);
+        Ok(params)
     }
 
     fn double_symbol(sym1: (Token, &str), sym2: (Token, &str)) -> Result<(), Box<dyn std::error::Error>> {
@@ -324,41 +843,143 @@ impl Ast {
             ||
            sym2.0.is_symbol(sym2.1).is_err()
         {
-            let loc = sym1.0.loc();
-            return Err(format!("{} expected `{}{}`", log_color(loc), sym1.1, sym2.1).into());
+            let loc = Loc::of(&sym1.0);
+            return Err(Diagnostic::error(loc, format!("expected `{}{}`", sym1.1, sym2.1)).into());
+        }
+        Ok(())
+    }
+
+    // `name`, `module::name` or `Enum::Variant` and the location of all of it, leaves the index after the last name
+    fn path(tokens: &[Token], index: &mut usize) -> Result<(Vec<String>, Loc), Box<dyn std::error::Error>> {
+        let start = Loc::of(&tokens[*index]);
+        let mut path: Vec<String> = vec![tokens[*index].is_ident()?];
+        *index += 1;
+        while *index + 1 < tokens.len() && tokens[*index].is_symbol("Colon").is_ok() && tokens[*index + 1].is_symbol("Colon").is_ok() {
+            *index += 2;
+            match tokens.get(*index).map(|token| token.is_ident()) {
+                Some(Ok(name)) => path.push(name),
+                _ => {
+                    let loc = Loc::of(&tokens[*index - 1]);
+                    return Err(Diagnostic::error(loc, "expected `ident` after `::`").into());
+                },
+            }
+            *index += 1;
         }
-        return Ok(());
+        Ok((path, start.to(Loc::of(&tokens[*index - 1]))))
+    }
+
+    // a statement starting with `name::name` is a expression, declarations continue with `(` or a keyword
+    fn is_path(tokens: &[Token], index: usize) -> bool {
+        return index + 3 < tokens.len()
+            && tokens[index + 1].is_symbol("Colon").is_ok()
+            && tokens[index + 2].is_symbol("Colon").is_ok()
+            && tokens[index + 3].is_ident().is_ok();
     }
 
     fn is_type(token: Token) -> Result<&'static str, Box<dyn std::error::Error>> {
-        if token.is_keyword("int").is_ok() {
-            return Ok("int");
-        } else if token.is_keyword("ptr").is_ok() {
-            return Ok("ptr");
+        for name in TYPES {
+            if token.is_keyword(name).is_ok() {
+                return Ok(name);
+            }
         }
-        let loc = token.loc();
-        return Err(format!("{} expected `type`", log_color(loc)).into());
+        let loc = Loc::of(&token);
+        Err(Diagnostic::error(loc, "expected `type`").into())
     }
 
     fn str_to_type(str_t: &str) -> Type {
-        return match str_t {
+        match str_t {
             "int" => Type::Int,
+            "i8" => Type::I8,
+            "i16" => Type::I16,
+            "i32" => Type::I32,
+            "i64" => Type::I64,
+            "u8" => Type::U8,
+            "u16" => Type::U16,
+            "u32" => Type::U32,
+            "u64" => Type::U64,
             "ptr" => Type::Ptr,
+            "bool" => Type::Bool,
             _ => Type::Void,
         }
     }
 
-    fn param(tokens: &Vec<Token>, global_loc: (usize, usize)) -> Result<(String, Type), Box<dyn std::error::Error>> {
+    // keyword types, arrays or the name of a struct
+    fn parse_type(tokens: &Vec<Token>, index: &mut usize) -> Result<Type, Box<dyn std::error::Error>> {
+        Self::nested_type(tokens, index, &mut false)
+    }
+
+    // `>>` closes two pointer types, `split` is set when the inner one has used half of it
+    fn nested_type(tokens: &Vec<Token>, index: &mut usize, split: &mut bool) -> Result<Type, Box<dyn std::error::Error>> {
+        if tokens[*index].is_symbol("OpenBracket").is_ok() {
+            // [type; length]
+            Self::bound_check(tokens, index, "type")?;
+            let elem_t = Self::parse_type(tokens, index)?;
+            if *index >= tokens.len() || tokens[*index].is_symbol("SemiColon").is_err() {
+                let loc = Loc::of(&tokens[*index - 1]);
+                return Err(Diagnostic::error(loc, "expected `;` in array type").into());
+            }
+            Self::bound_check(tokens, index, "integer")?;
+            let array_t = if let Ok(len) = tokens[*index].is_integer() {
+                Type::Array(Box::new(elem_t), len)
+            } else if let Ok(name) = tokens[*index].is_ident() {
+                Type::ConstArray(Box::new(elem_t), name, Loc::of(&tokens[*index]))
+            } else {
+                let loc = Loc::of(&tokens[*index]);
+                return Err(Diagnostic::error(loc, "expected `integer` or constant as array length").into());
+            };
+            Self::bound_check(tokens, index, "CloseBracket")?;
+            if tokens[*index].is_symbol("CloseBracket").is_err() {
+                let loc = Loc::of(&tokens[*index]);
+                return Err(Diagnostic::error(loc, "expected `]` in array type").into());
+            }
+            *index += 1;
+            return Ok(array_t);
+        } else if let Ok(name_t) = Self::is_type(tokens[*index].clone()) {
+            *index += 1;
+            if name_t != "ptr" || *index >= tokens.len() || tokens[*index].is_symbol("SThen").is_err() {
+                return Ok(Self::str_to_type(name_t));
+            }
+
+            // ptr<type>
+            Self::bound_check(tokens, index, "type")?;
+            let pointee_t = Self::nested_type(tokens, index, split)?;
+            if *index < tokens.len() && tokens[*index].is_symbol("ShiftRight").is_ok() {
+                if !*split {
+                    *split = true;
+                    return Ok(Type::TypedPtr(Box::new(pointee_t)));
+                }
+                *split = false;
+            } else if *index >= tokens.len() || tokens[*index].is_symbol("BThen").is_err() {
+                let loc = Loc::of(&tokens[*index - 1]);
+                return Err(Diagnostic::error(loc, "expected `>` in pointer type").into());
+            }
+            *index += 1;
+            return Ok(Type::TypedPtr(Box::new(pointee_t)));
+        } else if tokens[*index].is_ident().is_ok() {
+            // named types are parsed as structs, the resolver turns enums into `Enum`
+            let (path, loc) = Self::path(tokens, index)?;
+            return Ok(Type::Struct(Struct {
+                name: path.join("::"),
+                fields: Vec::new(),
+                loc,
+            }));
+        }
+        let loc = Loc::of(&tokens[*index]);
+        Err(Diagnostic::error(loc, "expected `type`").into())
+    }
+
+    // the type of a variadic parameter `name -> ...` is left out
+    fn param(tokens: &Vec<Token>, global_loc: Loc) -> Result<(String, Option<Type>), Box<dyn std::error::Error>> {
         let mut index = 0;
-        if tokens.len() == 0 {
-            return Err(format!("{} expected `ident`", log_color(global_loc)).into());
+        if tokens.is_empty() {
+            return Err(Diagnostic::error(global_loc, "expected `ident`").into());
         }
         // name -> type
         let name = match tokens[index].is_ident() {
             Ok(name) => name,
             Err(_) => {
-                let loc = tokens[index].loc();
-                return Err(format!("{} expected `ident`", log_color(loc)).into());
+                let loc = Loc::of(&tokens[index]);
+                return Err(Diagnostic::error(loc, "expected `ident`").into());
             },
         };
 
@@ -369,209 +990,560 @@ impl Ast {
 
         Self::bound_check(tokens, &mut index, "type")?;
 
-        let name_t = Self::is_type(tokens[index].clone())?;
+        if tokens[index].is_symbol("Ellipsis").is_ok() && index + 1 == tokens.len() {
+            return Ok((name, None));
+        }
+        let name_t = Self::parse_type(tokens, &mut index)?;
+        if index < tokens.len() {
+            let loc = Loc::of(&tokens[index]);
+            return Err(Diagnostic::error(loc, format!("unexpected `{}` after type", loc.slice())).into());
+        }
 
-        return Ok((name, Self::str_to_type(name_t)));
+        Ok((name, Some(name_t)))
     }
 
-    fn scope(tokens: &Vec<Token>, index: &mut usize, open: &str, close: &str) -> Result<Vec<Token>, Box<dyn std::error::Error>> {
-        let mut scope_c = 0;
-        let mut scope: Vec<Token> = Vec::new();
+    // splits on the top level `=` or compound assignment, without one the statement is an expression
+    fn assignment(tokens: &Vec<Token>, loc: Loc) -> Result<Ast, Box<dyn std::error::Error>> {
+        let last = &tokens[tokens.len() - 1];
+        if last.is_symbol("PlusPlus").is_ok() || last.is_symbol("MinusMinus").is_ok() {
+            // INCREMENT AND DECREMENT
+            return Ok(Ast::Compound {
+                loc,
+                target: Self::expr(&tokens[..tokens.len() - 1].to_vec(), loc)?,
+                op: if last.is_symbol("PlusPlus").is_ok() { Operator::Plus } else { Operator::Minus },
+                value: Value::Int(1),
+            });
+        }
 
-        while (tokens[*index].is_symbol(close).is_err() && scope_c == 0) || scope_c != 0 {
-            if tokens[*index].is_symbol(open).is_ok() {
+        let mut scope_c = 0;
+        let mut split: Option<usize> = None;
+        for (index, token) in tokens.iter().enumerate() {
+            if token.is_symbol("OpenParen").is_ok() || token.is_symbol("OpenBracket").is_ok() {
                 scope_c += 1;
-            } else if tokens[*index].is_symbol(close).is_ok() {
+            } else if token.is_symbol("CloseParen").is_ok() || token.is_symbol("CloseBracket").is_ok() {
                 scope_c -= 1;
+            } else if (token.is_symbol("Equal").is_ok() || Self::is_compound(token).is_some()) && scope_c == 0 {
+                split = Some(index);
+                break;
             }
-            scope.push(tokens[*index].clone());
-
-            Self::bound_check(tokens, index, close)?;
         }
 
-        return Ok(scope);
-    }
+        let split = match split {
+            Some(split) => split,
+            None => {
+                return Ok(Ast::Expr {
+                    loc,
+                    value: Self::expr(tokens, loc)?,
+                });
+            },
+        };
 
-    fn is_comparison_op(token: &Token, loc: (usize, usize)) -> Result<(), Box<dyn std::error::Error>> {
-        return if token.is_symbol("Equal").is_ok() {
-            Ok(())
-        } else if token.is_symbol("Bang").is_ok() {
-            Ok(())
-        } else if token.is_symbol("BThen").is_ok() {
-            Ok(())
-        } else if token.is_symbol("SThen").is_ok() {
-            Ok(())
-        } else {
-            Err(format!("{} expected `Comparison Operator`", log_color(loc)).into())
+        let target = Self::expr(&tokens[..split].to_vec(), loc)?;
+        let value = Self::expr(&tokens[split + 1..].to_vec(), Loc::of(&tokens[split]))?;
+        if let Some(op) = Self::is_compound(&tokens[split]) {
+            return Ok(Ast::Compound {
+                loc,
+                target,
+                op,
+                value,
+            });
         }
+        Ok(match target {
+            Value::Ident {loc, name} => Ast::MutateVar {
+                loc,
+                name,
+                value,
+            },
+            Value::Deref {value: ptr, deref_t: ptr_type, ..} => Ast::MutatePtr {
+                loc,
+                ptr_type,
+                ptr: *ptr,
+                value,
+            },
+            target => Ast::Assign {
+                loc,
+                target,
+                value,
+            },
+        })
     }
 
-    fn comparison_op(tokens: &Vec<Token>, index: &mut usize) -> Result<ComparisonOp, Box<dyn std::error::Error>> {
-        if tokens[*index - 1].is_symbol("Equal").is_ok() && tokens[*index].is_symbol("Equal").is_ok() {
-            return Ok(ComparisonOp::Equal);
-        } else if tokens[*index - 1].is_symbol("Bang").is_ok() && tokens[*index].is_symbol("Equal").is_ok() {
-            return Ok(ComparisonOp::NotEqual);
-        } else if tokens[*index].is_symbol("BThen").is_ok() {
-            return Ok(ComparisonOp::Bigger);
-        } else if tokens[*index].is_symbol("SThen").is_ok() {
-            return Ok(ComparisonOp::Smaller);
-        } else {
-            let loc = tokens[*index].loc();
-            return Err(format!("{} expected `Comparison Operator`", log_color(loc)).into());
+    // parses a single statement that isnt terminated by a `;`
+    fn header_statement(tokens: &[Token]) -> Result<Vec<Ast>, Box<dyn std::error::Error>> {
+        if tokens.is_empty() {
+            return Ok(Vec::new());
         }
+        let mut statement = tokens.to_vec();
+        // the `;` keeps the file and position of the last token
+        statement.push(Token::Symbol(';', "SemiColon".to_string(), tokens[tokens.len() - 1].loc()));
+        Self::parse(&statement)
     }
 
-    fn parse_comparison(tokens: &Vec<Token>, loc: (usize, usize)) -> Result<Comparison, Box<dyn std::error::Error>> {
-        if tokens.len() == 0 {
-            return Err(format!("{} expected `Comparison`", log_color(loc)).into());
+    // init; condition; step
+    fn for_header(tokens: &[Token], loc: Loc) -> Result<LoopHeader, Box<dyn std::error::Error>> {
+        let mut parts: Vec<&[Token]> = Vec::new();
+        let mut scope_c = 0;
+        let mut start = 0;
+        for (index, token) in tokens.iter().enumerate() {
+            if token.is_symbol("OpenBracket").is_ok() || token.is_symbol("OpenParen").is_ok() {
+                scope_c += 1;
+            } else if token.is_symbol("CloseBracket").is_ok() || token.is_symbol("CloseParen").is_ok() {
+                scope_c -= 1;
+            } else if token.is_symbol("SemiColon").is_ok() && scope_c == 0 {
+                parts.push(&tokens[start..index]);
+                start = index + 1;
+            }
+        }
+        parts.push(&tokens[start..]);
+
+        if parts.len() != 3 {
+            return Err(Diagnostic::error(loc, "expected `init; condition; step` in for loop").into());
         }
 
-        let mut l_expr: Vec<Token> = Vec::new();
-        let mut r_expr: Vec<Token> = Vec::new();
+        // without a condition the loop runs until it is left with break
+        let condition = if parts[1].is_empty() {
+            Value::Bool(true)
+        } else {
+            Self::expr(&parts[1].to_vec(), Loc::of(&parts[1][0]))?
+        };
+
+        return Ok((Self::header_statement(parts[0])?, condition, Self::header_statement(parts[2])?));
+    }
+
+    // pattern [| pattern] => { body }, arms are separated by commas and `_` matches everything else
+    fn match_arms(tokens: &[Token], loc: Loc) -> Result<MatchArms, Box<dyn std::error::Error>> {
+        let mut arms: Vec<(Vec<Value>, Vec<Ast>)> = Vec::new();
+        let mut default: Option<Vec<Ast>> = None;
         let mut index = 0;
-        let loc = tokens[index].loc();
+        let is_wildcard = |token: &Token| token.is_ident().is_ok_and(|name| name == "_");
 
-        while Self::is_comparison_op(&tokens[index], tokens[index].loc()).is_err() {
-            l_expr.push(tokens[index].clone());
-            Self::bound_check(tokens, &mut index, "Comparison Operator")?;
+        while index < tokens.len() {
+            let arm_loc = Loc::of(&tokens[index]);
+            if default.is_some() {
+                return Err(Diagnostic::error(arm_loc, "the `_` arm has to be the last arm of a match").into());
+            }
+
+            // patterns are split at the top level `|` so they arent parsed as a bitwise or
+            let mut patterns: Vec<Vec<Token>> = vec![Vec::new()];
+            let mut scope_c = 0;
+            while tokens[index].is_symbol("FatArrow").is_err() {
+                if tokens[index].is_symbol("OpenParen").is_ok() {
+                    scope_c += 1;
+                } else if tokens[index].is_symbol("CloseParen").is_ok() {
+                    scope_c -= 1;
+                }
+                if tokens[index].is_symbol("Pipe").is_ok() && scope_c == 0 {
+                    patterns.push(Vec::new());
+                } else {
+                    patterns.last_mut().unwrap().push(tokens[index].clone());
+                }
+                if index + 1 >= tokens.len() {
+                    return Err(Diagnostic::error(arm_loc, "expected `=>` in match arm").into());
+                }
+                index += 1;
+            }
+
+            Self::bound_check(tokens, &mut index, "OpenBrace")?;
+            if tokens[index].is_symbol("OpenBrace").is_err() {
+                let loc = Loc::of(&tokens[index]);
+                return Err(Diagnostic::error(loc, format!("expected `{{` after `=>` but got `{}`", Loc::of(&tokens[index]).slice())).into());
+            }
+            Self::bound_check(tokens, &mut index, "CloseBrace")?;
+            let body = Self::parse(&Self::scope(tokens, &mut index, "OpenBrace", "CloseBrace")?)?;
+            index += 1;
+
+            // optional comma after the body
+            if index < tokens.len() && tokens[index].is_symbol("Comma").is_ok() {
+                index += 1;
+            }
+
+            if patterns.len() == 1 && patterns[0].len() == 1 && is_wildcard(&patterns[0][0]) {
+                default = Some(body);
+                continue;
+            }
+            let mut values: Vec<Value> = Vec::new();
+            for pattern in patterns {
+                if pattern.iter().any(is_wildcard) {
+                    return Err(Diagnostic::error(arm_loc, "`_` cant be combined with other patterns").into());
+                }
+                values.push(Self::expr(&pattern, arm_loc)?);
+            }
+            arms.push((values, body));
         }
 
-        // dont add index if the comparison is only 1 token
-        if tokens[index].is_symbol("BThen").is_err() && tokens[index].is_symbol("SThen").is_err() {
-            Self::bound_check(tokens, &mut index, "Comparison Operator")?;
+        if arms.is_empty() && default.is_none() {
+            return Err(Diagnostic::error(loc, "match needs at least one arm").into());
         }
+        Ok((arms, default))
+    }
 
-        let op = Self::comparison_op(tokens, &mut index)?;
+    // name [-> type] in start..end, the end is only evaluated once
+    fn range_header(tokens: &Vec<Token>, loc: Loc) -> Result<LoopHeader, Box<dyn std::error::Error>> {
+        let mut index = 0;
+        let name = match tokens[index].is_ident() {
+            Ok(name) => name,
+            Err(_) => {
+                let loc = Loc::of(&tokens[index]);
+                return Err(Diagnostic::error(loc, format!("expected `ident` but got `{}`", Loc::of(&tokens[index]).slice())).into());
+            },
+        };
+        Self::bound_check(tokens, &mut index, "in")?;
+
+        let var_t = if tokens[index].is_symbol("Minus").is_ok() {
+            Self::bound_check(tokens, &mut index, "BThen")?;
+            Self::double_symbol((tokens[index - 1].clone(), "Minus"), (tokens[index].clone(), "BThen"))?;
+            Self::bound_check(tokens, &mut index, "type")?;
+            let var_t = Self::parse_type(tokens, &mut index)?;
+            index -= 1; // parse_type leaves the index after the type
+            Self::bound_check(tokens, &mut index, "in")?;
+            var_t
+        } else {
+            Type::Int
+        };
 
-        Self::bound_check(tokens, &mut index, "Value")?;
+        if tokens[index].is_keyword("in").is_err() {
+            let loc = Loc::of(&tokens[index]);
+            return Err(Diagnostic::error(loc, format!("expected `in` but got `{}`", Loc::of(&tokens[index]).slice())).into());
+        }
+        Self::bound_check(tokens, &mut index, "range")?;
 
-        let r_loc = tokens[index].loc();
-        while index < tokens.len() {
-            r_expr.push(tokens[index].clone());
-            index += 1;
+        let range = &tokens[index..];
+        let split = match range.iter().position(|token| token.is_symbol("DotDot").is_ok()) {
+            Some(split) => split,
+            None => {
+                return Err(Diagnostic::error(loc, "expected `start..end` in for loop").into());
+            },
+        };
+        let start = Self::expr(&range[..split].to_vec(), Loc::of(&range[split]))?;
+        let end = Self::expr(&range[split + 1..].to_vec(), Loc::of(&range[split]))?;
+
+        // the name cant be written in the source so it doesnt collide with other variables
+        let end_name = format!("{}.end", name);
+        let init = vec![
+            Ast::Variable {
+                loc,
+                name: name.clone(),
+                var_t: var_t.clone(),
+                value: start,
+            },
+            Ast::Variable {
+                loc,
+                name: end_name.clone(),
+                var_t,
+                value: end,
+            },
+        ];
+        let condition = Value::Comparison {
+            loc,
+            l_expr: Box::new(Value::Ident {
+                loc,
+                name: name.clone(),
+            }),
+            r_expr: Box::new(Value::Ident {
+                loc,
+                name: end_name,
+            }),
+            op: ComparisonOp::Smaller,
+        };
+        let step = vec![
+            Ast::MutateVar {
+                loc,
+                name: name.clone(),
+                value: Value::BinaryExpr {
+                    loc,
+                    l_expr: Box::new(Value::Ident {
+                        loc,
+                        name,
+                    }),
+                    r_expr: Box::new(Value::Int(1)),
+                    op: Operator::Plus,
+                },
+            },
+        ];
+        Ok((init, condition, step))
+    }
+
+    // collects the tokens up to the `;` ending the statement, a `;` inside brackets belongs to an array type
+    fn until_semicolon(tokens: &[Token], index: &mut usize) -> Result<Vec<Token>, Box<dyn std::error::Error>> {
+        let mut scope_c = 0;
+        let mut value: Vec<Token> = Vec::new();
+
+        while scope_c != 0 || tokens[*index].is_symbol("SemiColon").is_err() {
+            if tokens[*index].is_symbol("OpenBracket").is_ok() {
+                scope_c += 1;
+            } else if tokens[*index].is_symbol("CloseBracket").is_ok() {
+                scope_c -= 1;
+            }
+            value.push(tokens[*index].clone());
+
+            Self::bound_check(tokens, index, "SemiColon")?;
         }
 
-        return Ok(Comparison {
-            l_expr: Box::new(Self::expr(&l_expr, loc)?),
-            r_expr: Box::new(Self::expr(&r_expr, r_loc)?),
-            op,
-        });
+        Ok(value)
+    }
+
+    fn scope(tokens: &[Token], index: &mut usize, open: &str, close: &str) -> Result<Vec<Token>, Box<dyn std::error::Error>> {
+        let mut scope_c = 0;
+        let mut scope: Vec<Token> = Vec::new();
+
+        while scope_c != 0 || tokens[*index].is_symbol(close).is_err() {
+            if tokens[*index].is_symbol(open).is_ok() {
+                scope_c += 1;
+            } else if tokens[*index].is_symbol(close).is_ok() {
+                scope_c -= 1;
+            }
+            scope.push(tokens[*index].clone());
+
+            Self::bound_check(tokens, index, close)?;
+        }
+
+        Ok(scope)
+    }
+
+    fn is_comparison_op(tokens: &[Token], index: usize) -> Result<ComparisonOp, Box<dyn std::error::Error>> {
+        if index >= tokens.len() {
+            return Err("expected `Comparison Operator`".into());
+        } else if tokens[index].is_symbol("EqualEqual").is_ok() {
+            return Ok(ComparisonOp::Equal);
+        } else if tokens[index].is_symbol("BangEqual").is_ok() {
+            return Ok(ComparisonOp::NotEqual);
+        } else if tokens[index].is_symbol("BThen").is_ok() {
+            return Ok(ComparisonOp::Bigger);
+        } else if tokens[index].is_symbol("SThen").is_ok() {
+            return Ok(ComparisonOp::Smaller);
+        } else if tokens[index].is_symbol("BThenEqual").is_ok() {
+            return Ok(ComparisonOp::BiggerEqual);
+        } else if tokens[index].is_symbol("SThenEqual").is_ok() {
+            return Ok(ComparisonOp::SmallerEqual);
+        }
+        let loc = Loc::of(&tokens[index]);
+        Err(Diagnostic::error(loc, "expected `Comparison Operator`").into())
+    }
+
+    fn is_logical_op(tokens: &[Token], index: usize) -> Result<LogicalOp, Box<dyn std::error::Error>> {
+        if index >= tokens.len() {
+            return Err("expected `Logical Operator`".into());
+        } else if tokens[index].is_symbol("AndAnd").is_ok() {
+            return Ok(LogicalOp::And);
+        } else if tokens[index].is_symbol("PipePipe").is_ok() {
+            return Ok(LogicalOp::Or);
+        }
+        let loc = Loc::of(&tokens[index]);
+        Err(Diagnostic::error(loc, "expected `Logical Operator`").into())
     }
 
     pub fn parse(tokens: &Vec<Token>) -> Result<Vec<Ast>, Box<dyn std::error::Error>> {
+        let mut diagnostics = Diagnostics::new();
+        let ast = Self::parse_recovering(tokens, &mut diagnostics);
+        diagnostics.finish(ast)
+    }
+
+    // the statements that parsed, the errors of the others are added to the diagnostics
+    pub fn parse_recovering(tokens: &Vec<Token>, diagnostics: &mut Diagnostics) -> Vec<Ast> {
         let mut ast: Vec<Ast> = Vec::new();
 
+        // label of the next loop
+        let mut loop_label: Option<String> = None;
+
         let mut index = 0;
         while index < tokens.len() {
-            if let Ok(name) = tokens[index].is_ident() {
-                Self::bound_check(tokens, &mut index, "Equal")?;
-                if tokens[index].is_symbol("Equal").is_ok() {
-                    // VARIABLE MUTATION
-                    let loc = tokens[index - 1].loc();
+            match Self::statement(tokens, index, &mut ast, &mut loop_label) {
+                Ok(next) => index = next,
+                Err(error) => {
+                    diagnostics.push(error);
+                    loop_label = None;
+                    index = Self::recover(tokens, index);
+                },
+            }
+        }
+        ast
+    }
+
+    // skips the statement that failed to parse, it ends at a `;` or at the `}` closing its body
+    fn recover(tokens: &[Token], start: usize) -> usize {
+        let mut depth = 0;
+        let mut index = start;
+        while index < tokens.len() {
+            if tokens[index].is_symbol("OpenBrace").is_ok() {
+                depth += 1;
+            } else if tokens[index].is_symbol("CloseBrace").is_ok() {
+                depth -= 1;
+                // an `else` continues the statement
+                let is_else = index + 1 < tokens.len() && tokens[index + 1].is_keyword("else").is_ok();
+                if depth <= 0 && !is_else {
+                    return index + 1;
+                }
+            } else if tokens[index].is_symbol("SemiColon").is_ok() && depth == 0 {
+                return index + 1;
+            }
+            index += 1;
+        }
+        index
+    }
 
-                    Self::bound_check(tokens, &mut index, "SemiColon")?;
+    fn statement(tokens: &Vec<Token>, mut index: usize, ast: &mut Vec<Ast>, loop_label: &mut Option<String>) -> Result<usize, Box<dyn std::error::Error>> {
+        if loop_label.is_some() && tokens[index].is_keyword("while").is_err() && tokens[index].is_keyword("for").is_err() {
+            let loc = Loc::of(&tokens[index]);
+            return Err(Diagnostic::error(loc, "expected a loop after the label").into());
+        }
 
-                    let mut value: Vec<Token> = Vec::new();
-                    while tokens[index].is_symbol("SemiColon").is_err() {
-                        value.push(tokens[index].clone());
-                        Self::bound_check(tokens, &mut index, "SemiColon")?;
-                    }
+        if let Ok(name) = tokens[index].is_ident() {
+            if index + 2 < tokens.len() && tokens[index + 1].is_symbol("Colon").is_ok() && tokens[index + 2].is_symbol("Colon").is_err() {
+                // LOOP LABEL
+                *loop_label = Some(name);
+                index += 2;
+                return Ok(index);
+            } else if index + 1 >= tokens.len() || tokens[index + 1].is_symbol("Colon").is_err() || Self::is_path(tokens, index) {
+                // ASSIGNMENT OR EXPRESSION STATEMENT
+                let loc = Loc::of(&tokens[index]);
 
-                    ast.push(Ast::MutateVar {
-                        loc,
-                        name,
-                        value: Self::expr(&value, loc)?,
-                    });
-                } else {
-                    index -= 1; // because we used bound_check in variable mutation
-                    // FUNCTIONS DECLARATIONS
-                    let loc = tokens[index].loc();
-                    let mut parameters: Vec<(String, Type)> = Vec::new();
+                let value = Self::until_semicolon(tokens, &mut index)?;
 
-                    Self::bound_check(tokens, &mut index, "Colon")?;
-                    Self::bound_check(tokens, &mut index, "Colon")?;
+                ast.push(Self::assignment(&value, loc)?);
+                index += 1;
+                return Ok(index);
+            }
+            let loc = Loc::of(&tokens[index]);
 
-                    // ::
-                    Self::double_symbol((tokens[index - 1].clone(), "Colon"), (tokens[index].clone(), "Colon"))?;
+            Self::bound_check(tokens, &mut index, "Colon")?;
+            Self::bound_check(tokens, &mut index, "Colon")?;
 
-                    Self::bound_check(tokens, &mut index, "OpenParen")?;
+            // ::
+            Self::double_symbol((tokens[index - 1].clone(), "Colon"), (tokens[index].clone(), "Colon"))?;
 
-                    // ()
-                    if tokens[index].is_symbol("OpenParen").is_ok() {
-                        let param_loc = tokens[index].loc();
-                        Self::bound_check(tokens, &mut index, "CloseParen")?;
-                        let mut param_tokens: Vec<Token> = Vec::new();
-                        while tokens[index].is_symbol("CloseParen").is_err() {
-                            if tokens[index].is_symbol("Comma").is_ok() {
-                                parameters.push(Self::param(&param_tokens, param_loc)?);
-                                param_tokens = Vec::new();
-                            } else {
-                                param_tokens.push(tokens[index].clone());
-                            }
-                            Self::bound_check(tokens, &mut index, "CloseParen")?;
-                        }
-                        if param_tokens.len() != 0 {
-                            parameters.push(Self::param(&param_tokens, param_loc)?);
+            Self::bound_check(tokens, &mut index, "OpenParen")?;
+
+            if tokens[index].is_keyword("struct").is_ok() {
+                // STRUCT DECLARATION
+                Self::bound_check(tokens, &mut index, "OpenBrace")?;
+                if tokens[index].is_symbol("OpenBrace").is_err() {
+                    let loc = Loc::of(&tokens[index]);
+                    return Err(Diagnostic::error(loc, "expected `{` in struct declaration").into());
+                }
+                let fields_loc = Loc::of(&tokens[index]);
+                Self::bound_check(tokens, &mut index, "CloseBrace")?;
+
+                // { }
+                let body = Self::scope(tokens, &mut index, "OpenBrace", "CloseBrace")?;
+
+                let mut fields: Vec<(String, Type)> = Vec::new();
+                // a trailing comma is allowed
+                for field in body.split(|token| token.is_symbol("Comma").is_ok()) {
+                    if !field.is_empty() {
+                        match Self::param(&field.to_vec(), fields_loc)? {
+                            (field_name, Some(field_t)) => fields.push((field_name, field_t)),
+                            (field_name, None) => {
+                                return Err(Diagnostic::error(fields_loc, format!("field `{}` cant be variadic", field_name)).into());
+                            },
                         }
-                    } else {
-                        let loc = tokens[index].loc();
-                        return Err(format!("{} expected `(` in function declaration", log_color(loc)).into());
                     }
-                    Self::bound_check(tokens, &mut index, "Minus")?;
-                    Self::bound_check(tokens, &mut index, "BThen")?;
+                }
 
-                    // ->
-                    Self::double_symbol((tokens[index - 1].clone(), "Minus"), (tokens[index].clone(), "BThen"))?;
-                    Self::bound_check(tokens, &mut index, "type")?;
+                ast.push(Ast::Struct {
+                    loc,
+                    name,
+                    fields,
+                });
+            } else if tokens[index].is_keyword("enum").is_ok() {
+                // ENUM DECLARATION
+                Self::bound_check(tokens, &mut index, "OpenBrace")?;
+                if tokens[index].is_symbol("OpenBrace").is_err() {
+                    let loc = Loc::of(&tokens[index]);
+                    return Err(Diagnostic::error(loc, "expected `{` in enum declaration").into());
+                }
+                Self::bound_check(tokens, &mut index, "CloseBrace")?;
 
-                    let return_t = Self::is_type(tokens[index].clone())?;
-                    Self::bound_check(tokens, &mut index, "OpenBrace")?;
+                // { }
+                let body = Self::scope(tokens, &mut index, "OpenBrace", "CloseBrace")?;
 
-                    if tokens[index].is_symbol("OpenBrace").is_err() {
-                        let loc = tokens[index].loc();
-                        return Err(format!("{} expected `{{` to start function body", log_color(loc)).into());
+                let mut variants: Vec<(String, Option<Value>)> = Vec::new();
+                // a trailing comma is allowed
+                for variant in body.split(|token| token.is_symbol("Comma").is_ok()) {
+                    if variant.is_empty() {
+                        continue;
                     }
-                    Self::bound_check(tokens, &mut index, "CloseBrace")?;
+                    let variant_loc = Loc::of(&variant[0]);
+                    let variant_name = match variant[0].is_ident() {
+                        Ok(variant_name) => variant_name,
+                        Err(_) => {
+                            return Err(Diagnostic::error(variant_loc, format!("expected `ident` but got `{}`", Loc::of(&variant[0]).slice())).into());
+                        },
+                    };
+
+                    // = [VALUE]
+                    let value = if variant.len() == 1 {
+                        None
+                    } else if variant[1].is_symbol("Equal").is_ok() {
+                        Some(Self::expr(&variant[2..].to_vec(), variant_loc)?)
+                    } else {
+                        let loc = Loc::of(&variant[1]);
+                        return Err(Diagnostic::error(loc, format!("expected `=` or `,` after enum variant but got `{}`", Loc::of(&variant[1]).slice())).into());
+                    };
+                    variants.push((variant_name, value));
+                }
 
-                    // { }
-                    let body = Self::scope(tokens, &mut index, "OpenBrace", "CloseBrace")?;
+                ast.push(Ast::Enum {
+                    loc,
+                    name,
+                    variants,
+                });
+            } else if tokens[index].is_keyword("const").is_ok() {
+                // CONSTANT DECLARATION
+                Self::bound_check(tokens, &mut index, "type")?;
+                let const_t = Self::parse_type(tokens, &mut index)?;
+                index -= 1; // parse_type leaves the index after the type
+                Self::bound_check(tokens, &mut index, "Equal")?;
 
-                    ast.push(Ast::Function {
-                        loc,
-                        name,
-                        param_t: parameters,
-                        return_t: Self::str_to_type(return_t),
-                        body: Self::parse(&body)?,
-                    });
+                // =
+                if tokens[index].is_symbol("Equal").is_err() {
+                    let loc = Loc::of(&tokens[index]);
+                    return Err(Diagnostic::error(loc, "expected `=` in constant declaration").into());
                 }
-            } else if tokens[index].is_keyword("return").is_ok() {
-                // RETURN STATEMENTS
-                let loc = tokens[index].loc();
-                let mut value: Vec<Token> = Vec::new();
                 Self::bound_check(tokens, &mut index, "SemiColon")?;
 
-                let value_loc = tokens[index].loc();
-                while tokens[index].is_symbol("SemiColon").is_err() {
-                    value.push(tokens[index].clone());
-                    Self::bound_check(tokens, &mut index, "SemiColon")?;
-                }
+                let value_loc = Loc::of(&tokens[index]);
+                let value = Self::until_semicolon(tokens, &mut index)?;
 
-                ast.push(Ast::Return {
+                ast.push(Ast::Const {
                     loc,
+                    name,
+                    const_t,
                     value: Self::expr(&value, value_loc)?,
                 });
-            } else if tokens[index].is_keyword("let").is_ok() {
-                // VARIABLE DECLARATION
-                let loc = tokens[index].loc();
-                Self::bound_check(tokens, &mut index, "ident")?;
-
-                // name
-                let name = if let Ok(ident) = tokens[index].is_ident() {
-                    ident
+            } else {
+                // FUNCTIONS DECLARATIONS
+                let mut parameters: Vec<(String, Type)> = Vec::new();
+                let mut variadic: Option<String> = None;
+
+                // ()
+                if tokens[index].is_symbol("OpenParen").is_ok() {
+                    let param_loc = Loc::of(&tokens[index]);
+                    Self::bound_check(tokens, &mut index, "CloseParen")?;
+                    let mut param_list: Vec<Vec<Token>> = vec![Vec::new()];
+                    while tokens[index].is_symbol("CloseParen").is_err() {
+                        if tokens[index].is_symbol("Comma").is_ok() {
+                            param_list.push(Vec::new());
+                        } else {
+                            param_list.last_mut().expect("internal compiler error").push(tokens[index].clone());
+                        }
+                        Self::bound_check(tokens, &mut index, "CloseParen")?;
+                    }
+                    if param_list.last().is_some_and(|param_tokens| param_tokens.is_empty()) {
+                        param_list.pop();
+                    }
+                    for param_tokens in &param_list {
+                        if let Some(variadic) = &variadic {
+                            return Err(Diagnostic::error(param_loc, format!("variadic parameter `{}` has to be the last parameter", variadic)).into());
+                        }
+                        match Self::param(param_tokens, param_loc)? {
+                            (name, Some(name_t)) => parameters.push((name, name_t)),
+                            (name, None) => variadic = Some(name),
+                        }
+                    }
                 } else {
-                    let loc = tokens[index].loc();
-                    return Err(format!("{} expected `ident` but got `{:?}`", log_color(loc), tokens[index]).into());
-                };
+                    let loc = Loc::of(&tokens[index]);
+                    return Err(Diagnostic::error(loc, "expected `(` in function declaration").into());
+                }
                 Self::bound_check(tokens, &mut index, "Minus")?;
                 Self::bound_check(tokens, &mut index, "BThen")?;
 
@@ -579,221 +1551,378 @@ impl Ast {
                 Self::double_symbol((tokens[index - 1].clone(), "Minus"), (tokens[index].clone(), "BThen"))?;
                 Self::bound_check(tokens, &mut index, "type")?;
 
-                // type
-                let var_t = Self::is_type(tokens[index].clone())?;
-                Self::bound_check(tokens, &mut index, "Equal")?;
+                let return_t = Self::parse_type(tokens, &mut index)?;
+                index -= 1; // parse_type leaves the index after the type
+                Self::bound_check(tokens, &mut index, "OpenBrace")?;
 
-                // =
-                if tokens[index].is_symbol("Equal").is_err() {
-                    let loc = tokens[index].loc();
-                    return Err(format!("{} expected `=` in variable declaration", log_color(loc)).into());
+                if tokens[index].is_symbol("OpenBrace").is_err() {
+                    let loc = Loc::of(&tokens[index]);
+                    return Err(Diagnostic::error(loc, "expected `{` to start function body").into());
                 }
-                Self::bound_check(tokens, &mut index, "SemiColon")?;
+                Self::bound_check(tokens, &mut index, "CloseBrace")?;
 
-                let value_loc = tokens[index].loc();
-                let mut value: Vec<Token> = Vec::new();
-                while tokens[index].is_symbol("SemiColon").is_err() {
-                    value.push(tokens[index].clone());
-                    Self::bound_check(tokens, &mut index, "SemiColon")?;
-                }
+                // { }
+                let body = Self::scope(tokens, &mut index, "OpenBrace", "CloseBrace")?;
+
+                ast.push(Ast::Function {
+                    loc,
+                    name,
+                    param_t: parameters,
+                    variadic,
+                    return_t,
+                    body: Self::parse(&body)?,
+                });
+            }
+        } else if tokens[index].is_keyword("return").is_ok() {
+            // RETURN STATEMENTS
+            let loc = Loc::of(&tokens[index]);
+            Self::bound_check(tokens, &mut index, "SemiColon")?;
+
+            let value_loc = Loc::of(&tokens[index]);
+            let value = Self::until_semicolon(tokens, &mut index)?;
+
+            ast.push(Ast::Return {
+                loc,
+                // `return;` is only valid in void functions
+                value: if value.is_empty() { Value::Null } else { Self::expr(&value, value_loc)? },
+            });
+        } else if tokens[index].is_keyword("let").is_ok() {
+            // VARIABLE DECLARATION
+            let loc = Loc::of(&tokens[index]);
+            Self::bound_check(tokens, &mut index, "ident")?;
+
+            // name
+            let name = if let Ok(ident) = tokens[index].is_ident() {
+                ident
+            } else {
+                let loc = Loc::of(&tokens[index]);
+                return Err(Diagnostic::error(loc, format!("expected `ident` but got `{}`", Loc::of(&tokens[index]).slice())).into());
+            };
+            Self::bound_check(tokens, &mut index, "Minus")?;
+            Self::bound_check(tokens, &mut index, "BThen")?;
 
+            // ->
+            Self::double_symbol((tokens[index - 1].clone(), "Minus"), (tokens[index].clone(), "BThen"))?;
+            Self::bound_check(tokens, &mut index, "type")?;
+
+            // type
+            let var_t = Self::parse_type(tokens, &mut index)?;
+            if index < tokens.len() && tokens[index].is_symbol("SemiColon").is_ok() {
+                // without a value the variable is zeroed
                 ast.push(Ast::Variable {
                     loc,
                     name,
-                    var_t: Self::str_to_type(var_t),
-                    value: Self::expr(&value, value_loc)?,
+                    var_t,
+                    value: Value::Null,
                 });
-            } else if tokens[index].is_keyword("if").is_ok() {
-                // IF STATEMENT
-                let loc = tokens[index].loc();
+                index += 1;
+                return Ok(index);
+            }
+            index -= 1; // parse_type leaves the index after the type
+            Self::bound_check(tokens, &mut index, "Equal")?;
 
-                Self::bound_check(tokens, &mut index, "OpenBrace")?;
+            // =
+            if tokens[index].is_symbol("Equal").is_err() {
+                let loc = Loc::of(&tokens[index]);
+                return Err(Diagnostic::error(loc, "expected `=` in variable declaration").into());
+            }
+            Self::bound_check(tokens, &mut index, "SemiColon")?;
 
-                let mut comparison: Vec<Token> = Vec::new();
-                let comparison_loc = tokens[index].loc();
-                while tokens[index].is_symbol("OpenBrace").is_err() {
-                    comparison.push(tokens[index].clone());
-                    Self::bound_check(tokens, &mut index, "OpenBrace")?;
-                }
+            let value_loc = Loc::of(&tokens[index]);
+            let value = Self::until_semicolon(tokens, &mut index)?;
 
-                Self::bound_check(tokens, &mut index, "CloseBrace")?;
+            ast.push(Ast::Variable {
+                loc,
+                name,
+                var_t,
+                value: Self::expr(&value, value_loc)?,
+            });
+        } else if tokens[index].is_keyword("if").is_ok() {
+            // IF STATEMENT
+            let loc = Loc::of(&tokens[index]);
 
-                // { }
-                let body = Self::scope(tokens, &mut index, "OpenBrace", "CloseBrace")?;
+            Self::bound_check(tokens, &mut index, "OpenBrace")?;
 
-                // no bound check because it will error if the if statement is at the end
-                index += 1;
-                let mut else_body: Vec<Token> = Vec::new();
-                // NOTE: COMPRESS THIS SOMEHOW
-                if index >= tokens.len() {
-                    ast.push(Ast::If {
-                        loc,
-                        comparison: Self::parse_comparison(&comparison, comparison_loc)?,
-                        body: Self::parse(&body)?,
-                        else_body: Vec::new(),
-                    });
-                } else if tokens[index].is_keyword("else").is_err() {
-                    ast.push(Ast::If {
-                        loc,
-                        comparison: Self::parse_comparison(&comparison, comparison_loc)?,
-                        body: Self::parse(&body)?,
-                        else_body: Vec::new(),
-                    });
-                    index -= 1;
-                } else if tokens[index].is_keyword("else").is_ok() {
-                    Self::bound_check(tokens, &mut index, "OpenBrace")?;
-                    if tokens[index].is_keyword("if").is_ok() {
-                        else_body.push(tokens[index].clone());
-                        Self::bound_check(tokens, &mut index, "Comparison")?;
+            let mut condition: Vec<Token> = Vec::new();
+            let condition_loc = Loc::of(&tokens[index]);
+            while tokens[index].is_symbol("OpenBrace").is_err() {
+                condition.push(tokens[index].clone());
+                Self::bound_check(tokens, &mut index, "OpenBrace")?;
+            }
 
-                        // modified version of scope function
-                        let mut indentation = 0;
+            Self::bound_check(tokens, &mut index, "CloseBrace")?;
 
-                        while index < tokens.len() {
-                            if tokens[index].is_symbol("OpenBrace").is_ok() {
-                                indentation += 1;
-                            } else if tokens[index].is_symbol("CloseBrace").is_ok() {
-                                indentation -= 1;
-                            }
-                            if tokens[index].is_symbol("CloseBrace").is_ok() && indentation == 0 {
-                                if index + 1 < tokens.len() {
-                                    if tokens[index + 1].is_keyword("else").is_err() {
-                                        else_body.push(tokens[index].clone());
-                                        break;
-                                    }
-                                } else {
+            // { }
+            let body = Self::scope(tokens, &mut index, "OpenBrace", "CloseBrace")?;
+
+            // no bound check because it will error if the if statement is at the end
+            index += 1;
+            let mut else_body: Vec<Token> = Vec::new();
+            // NOTE: COMPRESS THIS SOMEHOW
+            if index >= tokens.len() {
+                ast.push(Ast::If {
+                    loc,
+                    condition: Self::expr(&condition, condition_loc)?,
+                    body: Self::parse(&body)?,
+                    else_body: Vec::new(),
+                });
+            } else if tokens[index].is_keyword("else").is_err() {
+                ast.push(Ast::If {
+                    loc,
+                    condition: Self::expr(&condition, condition_loc)?,
+                    body: Self::parse(&body)?,
+                    else_body: Vec::new(),
+                });
+                index -= 1;
+            } else if tokens[index].is_keyword("else").is_ok() {
+                Self::bound_check(tokens, &mut index, "OpenBrace")?;
+                if tokens[index].is_keyword("if").is_ok() {
+                    else_body.push(tokens[index].clone());
+                    Self::bound_check(tokens, &mut index, "Comparison")?;
+
+                    // modified version of scope function
+                    let mut indentation = 0;
+
+                    while index < tokens.len() {
+                        if tokens[index].is_symbol("OpenBrace").is_ok() {
+                            indentation += 1;
+                        } else if tokens[index].is_symbol("CloseBrace").is_ok() {
+                            indentation -= 1;
+                        }
+                        if tokens[index].is_symbol("CloseBrace").is_ok() && indentation == 0 {
+                            if index + 1 < tokens.len() {
+                                if tokens[index + 1].is_keyword("else").is_err() {
                                     else_body.push(tokens[index].clone());
                                     break;
                                 }
+                            } else {
+                                else_body.push(tokens[index].clone());
+                                break;
                             }
-                            else_body.push(tokens[index].clone());
-                            // no bound checking on purpose
-                            // because the while loop checks it
-                            index += 1;
                         }
-                    } else {
-                        Self::bound_check(tokens, &mut index, "CloseBrace")?;
-
-                        else_body = Self::scope(tokens, &mut index, "OpenBrace", "CloseBrace")?;
+                        else_body.push(tokens[index].clone());
+                        // no bound checking on purpose
+                        // because the while loop checks it
+                        index += 1;
                     }
-                    ast.push(Ast::If {
-                        loc,
-                        comparison: Self::parse_comparison(&comparison, comparison_loc)?,
-                        body: Self::parse(&body)?,
-                        else_body: Self::parse(&else_body)?,
-                    });
+                } else {
+                    Self::bound_check(tokens, &mut index, "CloseBrace")?;
+
+                    else_body = Self::scope(tokens, &mut index, "OpenBrace", "CloseBrace")?;
                 }
-            } else if tokens[index].is_keyword("while").is_ok() {
-                // WHILE STATEMENT
-                let loc = tokens[index].loc();
+                ast.push(Ast::If {
+                    loc,
+                    condition: Self::expr(&condition, condition_loc)?,
+                    body: Self::parse(&body)?,
+                    else_body: Self::parse(&else_body)?,
+                });
+            }
+        } else if tokens[index].is_keyword("while").is_ok() {
+            // WHILE STATEMENT
+            let loc = Loc::of(&tokens[index]);
 
+            Self::bound_check(tokens, &mut index, "OpenBrace")?;
+
+            // while [STATEMENT] {
+            let mut condition: Vec<Token> = Vec::new();
+            let condition_loc = Loc::of(&tokens[index]);
+            while tokens[index].is_symbol("OpenBrace").is_err() {
+                condition.push(tokens[index].clone());
                 Self::bound_check(tokens, &mut index, "OpenBrace")?;
+            }
 
-                // while [STATEMENT] {
-                let mut comparison: Vec<Token> = Vec::new();
-                let comparison_loc = tokens[index].loc();
-                while tokens[index].is_symbol("OpenBrace").is_err() {
-                    comparison.push(tokens[index].clone());
-                    Self::bound_check(tokens, &mut index, "OpenBrace")?;
-                }
+            Self::bound_check(tokens, &mut index, "CloseBrace")?;
 
-                Self::bound_check(tokens, &mut index, "CloseBrace")?;
+            // { }
+            let body = Self::scope(tokens, &mut index, "OpenBrace", "CloseBrace")?;
 
-                // { }
-                let body = Self::scope(tokens, &mut index, "OpenBrace", "CloseBrace")?;
+            ast.push(Ast::While {
+                loc,
+                label: loop_label.take(),
+                condition: Self::expr(&condition, condition_loc)?,
+                body: Self::parse(&body)?,
+                step: Vec::new(),
+            });
+        } else if tokens[index].is_keyword("match").is_ok() {
+            // MATCH STATEMENT
+            let loc = Loc::of(&tokens[index]);
 
-                ast.push(Ast::While {
-                    loc,
-                    comparison: Self::parse_comparison(&comparison, comparison_loc)?,
-                    body: Self::parse(&body)?,
-                });
-            } else if let Ok(ptr_type) = Self::is_type(tokens[index].clone()) {
-                // POINTER MUTATION
-                let loc = tokens[index].loc();
-
-                // [
-                Self::bound_check(tokens, &mut index, "OpenBracket")?;
-                if tokens[index].is_symbol("OpenBracket").is_err() {
-                    return Err(format!("{} expected `OpenBracket`", log_color(loc)).into());
-                }
+            Self::bound_check(tokens, &mut index, "OpenBrace")?;
 
-                Self::bound_check(tokens, &mut index, "Value")?;
+            // match [VALUE] {
+            let mut value: Vec<Token> = Vec::new();
+            let value_loc = Loc::of(&tokens[index]);
+            while tokens[index].is_symbol("OpenBrace").is_err() {
+                value.push(tokens[index].clone());
+                Self::bound_check(tokens, &mut index, "OpenBrace")?;
+            }
 
-                // [ptr]
-                //let mut ptr: Vec<Token> = Vec::new();
-                //while tokens[index].is_symbol("CloseBracket").is_err() {
-                //    ptr.push(tokens[index].clone());
-                //    Self::bound_check(tokens, &mut index, "CloseBracket")?;
-                //}
-                let ptr = Self::scope(tokens, &mut index, "OpenBracket", "CloseBracket")?;
+            Self::bound_check(tokens, &mut index, "CloseBrace")?;
 
-                // =
-                Self::bound_check(tokens, &mut index, "Equal")?;
-                if tokens[index].is_symbol("Equal").is_err() {
-                    return Err(format!("{} expected `Equal`", log_color(loc)).into());
-                }
+            // { }
+            let arms = Self::scope(tokens, &mut index, "OpenBrace", "CloseBrace")?;
+            let (arms, default) = Self::match_arms(&arms, loc)?;
 
-                Self::bound_check(tokens, &mut index, "Value")?;
+            ast.push(Ast::Match {
+                loc,
+                value: Self::expr(&value, value_loc)?,
+                arms,
+                default,
+            });
+        } else if tokens[index].is_keyword("for").is_ok() {
+            // FOR LOOP
+            let loc = Loc::of(&tokens[index]);
 
-                // value;
-                let mut value: Vec<Token> = Vec::new();
-                while tokens[index].is_symbol("SemiColon").is_err() {
-                    value.push(tokens[index].clone());
-                    Self::bound_check(tokens, &mut index, "SemiColon")?;
-                }
+            Self::bound_check(tokens, &mut index, "OpenBrace")?;
 
-                ast.push(Ast::MutatePtr {
-                    loc,
-                    ptr_type: Self::str_to_type(ptr_type),
-                    ptr: Self::expr(&ptr, loc)?,
-                    value: Self::expr(&value, loc)?,
-                });
-            } else if tokens[index].is_keyword("include").is_ok() {
-                // INCLUDE
-                let loc = tokens[index].loc();
+            // for [HEADER] {
+            let mut header: Vec<Token> = Vec::new();
+            while tokens[index].is_symbol("OpenBrace").is_err() {
+                header.push(tokens[index].clone());
+                Self::bound_check(tokens, &mut index, "OpenBrace")?;
+            }
 
-                Self::bound_check(tokens, &mut index, "string")?;
+            Self::bound_check(tokens, &mut index, "CloseBrace")?;
 
-                let include_path = if let Ok(string) = tokens[index].is_section("string") {
-                    string
-                } else {
-                    return Err(format!("{} expected `string`", log_color(loc)).into());
-                };
+            // { }
+            let body = Self::scope(tokens, &mut index, "OpenBrace", "CloseBrace")?;
+
+            let (mut init, condition, step) = if header.iter().any(|token| token.is_keyword("in").is_ok()) {
+                Self::range_header(&header, loc)?
+            } else {
+                Self::for_header(&header, loc)?
+            };
+
+            // the block keeps the loop variable alive until the loop ends
+            init.push(Ast::While {
+                loc,
+                label: loop_label.take(),
+                condition,
+                body: Self::parse(&body)?,
+                step,
+            });
+            ast.push(Ast::Block {
+                loc,
+                body: init,
+            });
+        } else if tokens[index].is_keyword("break").is_ok() || tokens[index].is_keyword("continue").is_ok() {
+            // BREAK AND CONTINUE
+            let loc = Loc::of(&tokens[index]);
+            let is_break = tokens[index].is_keyword("break").is_ok();
+            Self::bound_check(tokens, &mut index, "SemiColon")?;
+
+            // optional label of the loop
+            let label = if let Ok(name) = tokens[index].is_ident() {
+                Self::bound_check(tokens, &mut index, "SemiColon")?;
+                Some(name)
+            } else {
+                None
+            };
 
-                ast.extend(generate_ast(&include_path));
-            } else if tokens[index].is_keyword("asm").is_ok() {
-                // INLINE ASM
-                let loc = tokens[index].loc();
+            if tokens[index].is_symbol("SemiColon").is_err() {
+                let loc = Loc::of(&tokens[index]);
+                return Err(Diagnostic::error(loc, format!("expected `;` but got `{}`", Loc::of(&tokens[index]).slice())).into());
+            }
 
-                Self::bound_check(tokens, &mut index, "OpenParen")?;
-                if tokens[index].is_symbol("OpenParen").is_err() {
-                    return Err(format!("{} expected `(` in function call", log_color(loc)).into());
+            ast.push(if is_break {
+                Ast::Break {
+                    loc,
+                    label,
                 }
+            } else {
+                Ast::Continue {
+                    loc,
+                    label,
+                }
+            });
+        } else if Self::is_type(tokens[index].clone()).is_ok() || tokens[index].is_symbol("Asterisk").is_ok() || tokens[index].is_symbol("OpenParen").is_ok() {
+            // POINTER MUTATION OR EXPRESSION STATEMENT
+            let loc = Loc::of(&tokens[index]);
+
+            let value = Self::until_semicolon(tokens, &mut index)?;
+
+            ast.push(Self::assignment(&value, loc)?);
+        } else if tokens[index].is_keyword("include").is_ok() {
+            // INCLUDE
+            let loc = Loc::of(&tokens[index]);
 
-                Self::bound_check(tokens, &mut index, "CloseParen")?;
+            Self::bound_check(tokens, &mut index, "string")?;
 
-                let asm = match tokens[index].is_section("string") {
-                    Ok(string) => string,
+            let include_path = if let Ok(string) = tokens[index].is_section("string") {
+                string
+            } else {
+                return Err(Diagnostic::error(loc, "expected `string`").into());
+            };
+
+            ast.push(Ast::Include {
+                loc,
+                path: include_path,
+            });
+        } else if tokens[index].is_keyword("import").is_ok() {
+            // IMPORT
+            let loc = Loc::of(&tokens[index]);
+            let mut path: Vec<String> = Vec::new();
+
+            Self::bound_check(tokens, &mut index, "ident")?;
+            loop {
+                match tokens[index].is_ident() {
+                    Ok(name) => path.push(name),
                     Err(_) => {
-                        return Err(format!("{} expected `string` in inline assembly", log_color(loc)).into());
+                        let loc = Loc::of(&tokens[index]);
+                        return Err(Diagnostic::error(loc, "expected `ident` in import").into());
                     },
-                };
-
-                Self::bound_check(tokens, &mut index, "CloseParen")?;
+                }
                 Self::bound_check(tokens, &mut index, "SemiColon")?;
+                if tokens[index].is_symbol("SemiColon").is_ok() {
+                    break;
+                } else if tokens[index].is_symbol("Dot").is_err() {
+                    let loc = Loc::of(&tokens[index]);
+                    return Err(Diagnostic::error(loc, format!("expected `.` or `;` but got `{}`", Loc::of(&tokens[index]).slice())).into());
+                }
+                Self::bound_check(tokens, &mut index, "ident")?;
+            }
 
-                Self::double_symbol((tokens[index - 1].clone(), "CloseParen"), (tokens[index].clone(), "SemiColon"))?;
+            ast.push(Ast::Import {
+                loc,
+                path,
+            });
+        } else if tokens[index].is_keyword("pub").is_ok() {
+            // PUBLIC DECLARATION
+            ast.push(Ast::Pub {
+                loc: Loc::of(&tokens[index]),
+            });
+        } else if tokens[index].is_keyword("asm").is_ok() {
+            // INLINE ASM
+            let loc = Loc::of(&tokens[index]);
 
-                ast.push(Ast::InlineAsm {
-                    loc,
-                    asm,
-                });
-            } else if tokens[index].is_section("comment").is_ok() {
-                // skip the comment
+            Self::bound_check(tokens, &mut index, "OpenParen")?;
+            if tokens[index].is_symbol("OpenParen").is_err() {
+                return Err(Diagnostic::error(loc, "expected `(` in function call").into());
             }
-            index += 1;
+
+            Self::bound_check(tokens, &mut index, "CloseParen")?;
+
+            let asm = match tokens[index].is_section("string") {
+                Ok(string) => string,
+                Err(_) => {
+                    return Err(Diagnostic::error(loc, "expected `string` in inline assembly").into());
+                },
+            };
+
+            Self::bound_check(tokens, &mut index, "CloseParen")?;
+            Self::bound_check(tokens, &mut index, "SemiColon")?;
+
+            Self::double_symbol((tokens[index - 1].clone(), "CloseParen"), (tokens[index].clone(), "SemiColon"))?;
+
+            ast.push(Ast::InlineAsm {
+                loc,
+                asm,
+            });
+        } else if tokens[index].is_section("comment").is_ok() {
+            // skip the comment
         }
-        return Ok(ast);
+        index += 1;
+        Ok(index)
     }
 }
 
@@ -0,0 +1,208 @@
+use crate::source::Loc;
+use std::io::IsTerminal;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+const RED: &str = "\x1b[1;31m";
+const BLUE: &str = "\x1b[1;34m";
+const BOLD: &str = "\x1b[1m";
+const RESET: &str = "\x1b[0;0m";
+
+static COLOR: AtomicBool = AtomicBool::new(false);
+// most errors printed for one compile, 0 prints all of them
+static LIMIT: AtomicUsize = AtomicUsize::new(20);
+
+// `--color=auto|always|never`, auto only colors when printing to a terminal
+pub fn set_color(choice: &str) {
+    let color = match choice {
+        "always" => true,
+        "never" => false,
+        _ => std::io::stdout().is_terminal(),
+    };
+    COLOR.store(color, Ordering::Relaxed);
+}
+
+pub fn set_limit(limit: usize) {
+    LIMIT.store(limit, Ordering::Relaxed);
+}
+
+fn paint(color: &str, text: &str) -> String {
+    if COLOR.load(Ordering::Relaxed) {
+        return format!("{color}{text}{RESET}");
+    }
+    text.to_string()
+}
+
+// a error pointing at the source, with secondary labels, notes and help text
+#[derive(Debug, Clone)]
+pub struct Diagnostic {
+    pub loc: Loc,
+    pub message: String,
+    pub labels: Vec<(Loc, String)>,
+    pub notes: Vec<String>,
+    pub help: Option<String>,
+}
+
+impl Diagnostic {
+    pub fn error(loc: Loc, message: impl Into<String>) -> Diagnostic {
+        Diagnostic {
+            loc,
+            message: message.into(),
+            labels: Vec::new(),
+            notes: Vec::new(),
+            help: None,
+        }
+    }
+
+    pub fn label(mut self, loc: Loc, label: impl Into<String>) -> Diagnostic {
+        self.labels.push((loc, label.into()));
+        self
+    }
+
+    pub fn note(mut self, note: impl Into<String>) -> Diagnostic {
+        self.notes.push(note.into());
+        self
+    }
+
+    pub fn help(mut self, help: impl Into<String>) -> Diagnostic {
+        self.help = Some(help.into());
+        self
+    }
+
+    // the source line with the span underlined, tabs are kept so the underline lines up
+    fn snippet(&self, loc: Loc, marker: char, label: &str, color: &str, gutter: usize) -> String {
+        let bar = paint(BLUE, &format!("{:gutter$} |", ""));
+        let Some(text) = loc.text() else {
+            return String::new();
+        };
+        let indent: String = text.chars()
+            .take(loc.col.saturating_sub(1))
+            .map(|character| if character == '\t' { '\t' } else { ' ' })
+            .collect();
+        let underline = marker.to_string().repeat(loc.len.max(1));
+        let label = if label.is_empty() { String::new() } else { format!(" {}", label) };
+        format!(
+            "{}\n{} {}\n{} {}{}\n",
+            bar,
+            paint(BLUE, &format!("{:>gutter$} |", loc.line)),
+            text,
+            bar,
+            indent,
+            paint(color, &format!("{}{}", underline, label)),
+        )
+    }
+
+    pub fn render(&self) -> String {
+        let gutter = self.labels.iter()
+            .map(|(loc, _)| loc.line)
+            .chain([self.loc.line])
+            .max()
+            .unwrap_or_default()
+            .to_string()
+            .len();
+        let mut output = format!("{}{}\n", paint(RED, "error"), paint(BOLD, &format!(": {}", self.message)));
+        output += &format!("{}{} {}:{}:{}\n", " ".repeat(gutter), paint(BLUE, "-->"), self.loc.path(), self.loc.line, self.loc.col);
+        output += &self.snippet(self.loc, '^', "", RED, gutter);
+
+        for (loc, label) in &self.labels {
+            if loc.file != self.loc.file {
+                output += &format!("{}{} {}:{}:{}\n", " ".repeat(gutter), paint(BLUE, ":::"), loc.path(), loc.line, loc.col);
+            }
+            output += &self.snippet(*loc, '-', label, BLUE, gutter);
+        }
+
+        let notes: Vec<(&str, &String)> = self.notes.iter()
+            .map(|note| ("note", note))
+            .chain(self.help.iter().map(|help| ("help", help)))
+            .collect();
+        if !notes.is_empty() {
+            output += &format!("{}\n", paint(BLUE, &format!("{:gutter$} |", "")));
+        }
+        for (kind, text) in notes {
+            output += &format!("{} {} {}\n", " ".repeat(gutter), paint(BLUE, "="), paint(BOLD, &format!("{}: {}", kind, text)));
+        }
+        output
+    }
+}
+
+impl std::fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}:{}: {}", self.loc.path(), self.loc.line, self.loc.col, self.message)
+    }
+}
+
+impl std::error::Error for Diagnostic {}
+
+// errors collected by a pass that keeps going after the first one
+#[derive(Debug, Default)]
+pub struct Diagnostics {
+    pub errors: Vec<Box<dyn std::error::Error>>,
+}
+
+impl Diagnostics {
+    pub fn new() -> Diagnostics {
+        Diagnostics::default()
+    }
+
+    // the errors of a nested pass are added one by one
+    pub fn push(&mut self, error: Box<dyn std::error::Error>) {
+        match error.downcast::<Diagnostics>() {
+            Ok(nested) => self.errors.extend(nested.errors),
+            Err(error) => self.errors.push(error),
+        }
+    }
+
+    // keeps the error of the result and goes on
+    pub fn collect<T>(&mut self, result: Result<T, Box<dyn std::error::Error>>) -> Option<T> {
+        match result {
+            Ok(value) => Some(value),
+            Err(error) => {
+                self.push(error);
+                None
+            },
+        }
+    }
+
+    pub fn finish<T>(self, value: T) -> Result<T, Box<dyn std::error::Error>> {
+        if self.errors.is_empty() {
+            return Ok(value);
+        }
+        Err(Box::new(self))
+    }
+}
+
+impl std::fmt::Display for Diagnostics {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let errors: Vec<String> = self.errors.iter().map(|error| error.to_string()).collect();
+        write!(f, "{}", errors.join("\n"))
+    }
+}
+
+impl std::error::Error for Diagnostics {}
+
+fn emit_one(error: &(dyn std::error::Error + 'static)) {
+    match error.downcast_ref::<Diagnostic>() {
+        Some(diagnostic) => println!("{}", diagnostic.render()),
+        None => println!("{}{}", paint(RED, "error"), paint(BOLD, &format!(": {}", error))),
+    }
+}
+
+// errors without a location are printed as just the message
+pub fn emit(error: &(dyn std::error::Error + 'static)) {
+    let Some(diagnostics) = error.downcast_ref::<Diagnostics>() else {
+        emit_one(error);
+        return;
+    };
+    let limit = match LIMIT.load(Ordering::Relaxed) {
+        0 => diagnostics.errors.len(),
+        limit => limit.min(diagnostics.errors.len()),
+    };
+    for error in &diagnostics.errors[..limit] {
+        emit_one(error.as_ref());
+    }
+    let summary = if limit < diagnostics.errors.len() {
+        format!("aborting after {} errors, {} more not shown", limit, diagnostics.errors.len() - limit)
+    } else {
+        format!("aborting due to {} previous error(s)", limit)
+    };
+    println!("{}{}", paint(RED, "error"), paint(BOLD, &format!(": {}", summary)));
+}
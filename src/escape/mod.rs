@@ -12,7 +12,7 @@ pub fn output_string_asm(string: &str) -> String {
             if index >= bytes.len() {
                 return result;
             } else if bytes[index] as char == 'n' {
-                result = result + "\", 10, \"";
+                result += "\", 10, \"";
             }
         } else {
             result = result + &(bytes[index] as char).to_string();
@@ -20,7 +20,7 @@ pub fn output_string_asm(string: &str) -> String {
         index += 1;
     }
 
-    return result;
+    result
 }
 
 
@@ -0,0 +1,138 @@
+use crate::ast::{Ast, Operator, Type, UnaryOp, Value};
+use crate::source::Loc;
+
+// literal expressions containing a negation are computed as signed integers
+pub fn is_signed(value: &Value) -> bool {
+    match value {
+        Value::Unary {op: UnaryOp::Neg, ..} => true,
+        Value::Unary {value, ..} => is_signed(value),
+        Value::BinaryExpr {l_expr, r_expr, ..} => is_signed(l_expr) || is_signed(r_expr),
+        _ => false,
+    }
+}
+
+// the value of a integer expression made of literals, arithmetic wraps around like it does at runtime
+pub fn evaluate(value: &Value) -> Option<usize> {
+    match value {
+        Value::Int(integer) => Some(*integer),
+        Value::Sizeof {size_t, ..} => Some(size_t.size()),
+        Value::Variant {enum_t: Type::Enum(enum_t), variant, ..} => enum_t.variant(variant),
+        Value::BinaryExpr {l_expr, r_expr, op, ..} => {
+            let (l_val, r_val) = (evaluate(l_expr)?, evaluate(r_expr)?);
+            let signed = is_signed(l_expr) || is_signed(r_expr);
+            match op {
+                Operator::Plus => Some(l_val.wrapping_add(r_val)),
+                Operator::Minus => Some(l_val.wrapping_sub(r_val)),
+                Operator::Multiplication => Some(l_val.wrapping_mul(r_val)),
+                // division by zero is left for the runtime
+                Operator::Divide if signed => (l_val as i64).checked_div(r_val as i64).map(|val| val as usize),
+                Operator::Divide => l_val.checked_div(r_val),
+                Operator::Modulo if signed => (l_val as i64).checked_rem(r_val as i64).map(|val| val as usize),
+                Operator::Modulo => l_val.checked_rem(r_val),
+                Operator::BitAnd => Some(l_val & r_val),
+                Operator::BitOr => Some(l_val | r_val),
+                Operator::BitXor => Some(l_val ^ r_val),
+                // the shift count is masked to 6 bits like it is by the cpu
+                Operator::ShiftLeft => Some(l_val.wrapping_shl(r_val as u32)),
+                Operator::ShiftRight if is_signed(l_expr) => Some((l_val as i64).wrapping_shr(r_val as u32) as usize),
+                Operator::ShiftRight => Some(l_val.wrapping_shr(r_val as u32)),
+            }
+        },
+        Value::Unary {value, op: UnaryOp::Neg, ..} => Some(evaluate(value)?.wrapping_neg()),
+        Value::Unary {value, op: UnaryOp::BitNot, ..} => Some(!evaluate(value)?),
+        _ => None,
+    }
+}
+
+// wraps a integer around to the width of the type, signed integers are sign extended like they are in registers
+pub fn truncate(integer: usize, value_t: &Type) -> usize {
+    let bits = (value_t.size() * 8) as u32;
+    if bits >= 64 {
+        return integer;
+    } else if value_t.is_signed() {
+        return (((integer as i64) << (64 - bits)) >> (64 - bits)) as usize;
+    }
+    integer & ((1 << bits) - 1)
+}
+
+// the folded literal of a expression, negative values stay a negation so they keep their sign
+pub fn literal(value: &Value, loc: Loc) -> Option<Value> {
+    let integer = evaluate(value)?;
+    if is_signed(value) && (integer as i64) < 0 {
+        return Some(Value::Unary {
+            loc,
+            value: Box::new(Value::Int(integer.wrapping_neg())),
+            op: UnaryOp::Neg,
+        });
+    }
+    Some(Value::Int(integer))
+}
+
+fn fold_value(value: &mut Value) {
+    match value {
+        Value::BinaryExpr {l_expr, r_expr, ..}
+        | Value::Comparison {l_expr, r_expr, ..}
+        | Value::Logical {l_expr, r_expr, ..} => {
+            fold_value(l_expr);
+            fold_value(r_expr);
+        },
+        Value::Index {value, index, ..} => {
+            fold_value(value);
+            fold_value(index);
+        },
+        Value::Unary {value, ..} | Value::Field {value, ..} | Value::Load {value, ..} => fold_value(value),
+        Value::Cast {value, ..} | Value::Deref {value, ..} | Value::Ref {value, ..} => fold_value(value),
+        Value::FunctionCall {params, ..} => {
+            for param in params {
+                fold_value(param);
+            }
+        },
+        Value::Sizeof {..} | Value::Variant {..} | Value::Int(_) | Value::Bool(_) | Value::Str(_) | Value::Ident {..} | Value::Null => {},
+    }
+
+    if let Value::BinaryExpr {loc, ..} | Value::Unary {loc, op: UnaryOp::Neg | UnaryOp::BitNot, ..} = *value {
+        if let Some(literal) = literal(value, loc) {
+            *value = literal;
+        }
+    }
+}
+
+// replaces binary expressions of literals with their value so they dont have to be computed at runtime
+pub fn fold(ast: &mut [Ast]) {
+    for instruction in ast.iter_mut() {
+        match instruction {
+            Ast::Function {body, ..} => fold(body),
+            Ast::Return {value, ..}
+            | Ast::Variable {value, ..}
+            | Ast::MutateVar {value, ..}
+            | Ast::Expr {value, ..} => fold_value(value),
+            Ast::MutatePtr {ptr: target, value, ..} | Ast::Assign {target, value, ..} | Ast::Compound {target, value, ..} => {
+                fold_value(target);
+                fold_value(value);
+            },
+            Ast::If {condition, body, else_body, ..} => {
+                fold_value(condition);
+                fold(body);
+                fold(else_body);
+            },
+            Ast::While {condition, body, step, ..} => {
+                fold_value(condition);
+                fold(body);
+                fold(step);
+            },
+            Ast::Match {value, arms, default, ..} => {
+                fold_value(value);
+                for (patterns, body) in arms {
+                    patterns.iter_mut().for_each(fold_value);
+                    fold(body);
+                }
+                if let Some(default) = default {
+                    fold(default);
+                }
+            },
+            Ast::Block {body, ..} => fold(body),
+            Ast::Struct {..} | Ast::Const {..} | Ast::Enum {..} | Ast::Break {..} | Ast::Continue {..} | Ast::InlineAsm {..} | Ast::Include {..} | Ast::Import {..} | Ast::Pub {..} => {},
+        }
+    }
+}
+
@@ -1,4 +1,84 @@
 use lib_lexin::{Lexer, Section, Token};
+use crate::source;
+use std::fs;
+
+// lib_lexin only supports single character symbols, these are joined
+// together when they are written right next to each other
+const COMPOUND_SYMBOLS: [(&str, &str, &str); 23] = [
+    ("Equal", "Equal", "EqualEqual"),
+    ("Bang", "Equal", "BangEqual"),
+    ("BThen", "Equal", "BThenEqual"),
+    ("SThen", "Equal", "SThenEqual"),
+    ("And", "And", "AndAnd"),
+    ("Pipe", "Pipe", "PipePipe"),
+    ("Dot", "Dot", "DotDot"),
+    ("DotDot", "Dot", "Ellipsis"),
+    ("Plus", "Plus", "PlusPlus"),
+    ("Minus", "Minus", "MinusMinus"),
+    ("BThen", "BThen", "ShiftRight"),
+    ("SThen", "SThen", "ShiftLeft"),
+    ("Equal", "BThen", "FatArrow"),
+
+    // compound assignments
+    ("Plus", "Equal", "PlusEqual"),
+    ("Minus", "Equal", "MinusEqual"),
+    ("Asterisk", "Equal", "AsteriskEqual"),
+    ("Slash", "Equal", "SlashEqual"),
+    ("Percent", "Equal", "PercentEqual"),
+    ("And", "Equal", "AndEqual"),
+    ("Pipe", "Equal", "PipeEqual"),
+    ("Caret", "Equal", "CaretEqual"),
+    ("ShiftLeft", "Equal", "ShiftLeftEqual"),
+    ("ShiftRight", "Equal", "ShiftRightEqual"),
+];
+
+fn join_symbols(tokens: Vec<Token>) -> Vec<Token> {
+    let mut joined: Vec<Token> = Vec::new();
+    // location of the last character of the previous token
+    let mut last_end = (0, 0);
+
+    for token in tokens {
+        if let (Some(Token::Symbol(character, prev_name, prev_loc)), Token::Symbol(_, name, loc)) = (joined.last(), &token) {
+            let adjacent = last_end.0 == loc.0 && last_end.1 + 1 == loc.1;
+            let compound = COMPOUND_SYMBOLS.iter().find(|symbol| symbol.0 == prev_name && symbol.1 == name);
+            if let (true, Some(compound)) = (adjacent, compound) {
+                let symbol = Token::Symbol(*character, compound.2.to_string(), *prev_loc);
+                last_end = *loc;
+                joined.pop();
+                joined.push(symbol);
+                continue;
+            }
+        }
+        last_end = token.loc();
+        joined.push(token);
+    }
+
+    joined
+}
+
+fn symbol_len(name: &str) -> usize {
+    match COMPOUND_SYMBOLS.iter().find(|symbol| symbol.2 == name) {
+        Some(compound) => symbol_len(compound.0) + symbol_len(compound.1),
+        None => 1,
+    }
+}
+
+// lib_lexin gives the position of the last character of a word, or of the newline after it
+// at the end of a line, the position is moved to the first character
+fn span(token: &Token, lines: &[&str]) -> (usize, usize, usize) {
+    let (line, col) = token.loc();
+    let len = match token {
+        Token::Symbol(_, name, _) => return (line, col, symbol_len(name)),
+        Token::Section(_, text, _) => text.len() + 2,
+        Token::Keyword(word, _) | Token::Ident(word, _) => word.len(),
+        Token::Integer(integer, _) => integer.to_string().len(),
+        Token::Float(float, _) => float.to_string().len(),
+    };
+    let text = lines.get(line.saturating_sub(1)).map(|text| text.as_bytes()).unwrap_or_default();
+    let at_end = text.get(col.saturating_sub(1)).is_none_or(|byte| byte.is_ascii_whitespace());
+    let end = if at_end && !matches!(token, Token::Section(..)) { col - 1 } else { col };
+    (line, (end + 1).saturating_sub(len).max(1), len)
+}
 
 pub fn lex(file: &str) -> Result<Vec<Token>, Box<dyn std::error::Error>> {
     let mut lexer = Lexer::new(
@@ -8,12 +88,37 @@ pub fn lex(file: &str) -> Result<Vec<Token>, Box<dyn std::error::Error>> {
             "if",
             "else",
             "while",
+            "break",
+            "continue",
+            "for",
+            "in",
+            "match",
             "include",
+            "import",
+            "pub",
             "asm",
+            "struct",
+            "enum",
+            "sizeof",
+            "const",
 
             // Types
             "int",
+            "i8",
+            "i16",
+            "i32",
+            "i64",
+            "u8",
+            "u16",
+            "u32",
+            "u64",
             "ptr",
+            "bool",
+            "void",
+
+            // Literals
+            "true",
+            "false",
         ],
         &[
             Section::new(
@@ -30,6 +135,7 @@ pub fn lex(file: &str) -> Result<Vec<Token>, Box<dyn std::error::Error>> {
         &[
             (',', "Comma"),
             (':', "Colon"),
+            ('.', "Dot"),
             (';', "SemiColon"),
             ('{', "OpenBrace"),
             ('}', "CloseBrace"),
@@ -42,8 +148,12 @@ pub fn lex(file: &str) -> Result<Vec<Token>, Box<dyn std::error::Error>> {
             ('+', "Plus"),
             ('*', "Asterisk"),
             ('/', "Slash"),
+            ('%', "Percent"),
+            ('^', "Caret"),
             ('!', "Bang"),
+            ('~', "Tilde"),
             ('&', "And"),
+            ('|', "Pipe"),
             ('=', "Equal"),
 
             ('>', "BThen"),
@@ -51,9 +161,16 @@ pub fn lex(file: &str) -> Result<Vec<Token>, Box<dyn std::error::Error>> {
         ],
     );
 
-    lexer.load_file(file)?;
+    let text = fs::read_to_string(file)?;
+    lexer.load_str(&text);
+    let id = source::add(file, &text);
 
-    return lexer.tokenize();
+    let lines: Vec<&str> = text.lines().collect();
+    let tokens = join_symbols(lexer.tokenize()?);
+    Ok(tokens.into_iter().map(|token| {
+        let span = span(&token, &lines);
+        source::attach(token, id, span)
+    }).collect())
 }
 
 
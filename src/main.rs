@@ -1,35 +1,27 @@
 mod lexer;
 mod ast;
 mod asm;
+mod resolve;
 mod typecheck;
 mod escape;
+mod fold;
+mod module;
+mod source;
+mod diagnostic;
 
 use argin::Argin;
+use std::path::PathBuf;
 use std::process;
-use std::collections::HashMap;
-use std::fs::File;
-use std::io::BufWriter;
 
-const RED: &'static str = "\x1b[1;31m";
-const YELLOW: &'static str = "\x1b[1;33m";
-const RESET: &'static str = "\x1b[0;0m";
+const COLORS: [&str; 3] = ["auto", "always", "never"];
 
 
 // _______ //
 // LOGGING //
 // _______ //
 
-fn log_color(loc: (usize, usize)) -> String {
-    return format!("{RED}{}{RESET}:{YELLOW}{}{RESET}:{RESET}", loc.0, loc.1);
-}
-
-fn error(error: &Box<dyn std::error::Error>) -> bool {
-    println!("[ERROR]: {}", error.to_string());
-    process::exit(1);
-}
-
-fn error_no_log(error: &Box<dyn std::error::Error>) -> bool {
-    println!("{}", error.to_string());
+fn error(error: &(dyn std::error::Error + 'static)) -> bool {
+    diagnostic::emit(error);
     process::exit(1);
 }
 
@@ -44,13 +36,21 @@ fn cli() -> Argin {
     args.add_positional_arg();
     args.add_flag("-r");
     args.add_flag("-o");
-    return args.parse();
+    args.add_value("-I");
+    args.add_value("--error-limit");
+    for color in COLORS {
+        args.add_flag(&format!("--color={}", color));
+    }
+    args.parse()
 }
 
 fn help() {
     println!("Usage: ./langin [FILE] [OPTIONS]");
     println!("    -r: run the final executable");
-    println!("    -o: optimizations");
+    println!("    -o: fold constant expressions");
+    println!("    -I [DIRS]: search the `:` separated directories for includes and imports");
+    println!("    --color=auto|always|never: color the error messages, auto colors them in a terminal");
+    println!("    --error-limit [N]: print at most N errors, 0 prints all of them (default 20)");
 }
 
 
@@ -58,37 +58,63 @@ fn help() {
 // COMPILING //
 // _________ //
 
-fn generate_ast(file: &str) -> Vec<ast::Ast> {
+// the statements that parsed, the errors of the file are added to the diagnostics
+fn generate_ast(file: &str, diagnostics: &mut diagnostic::Diagnostics) -> Vec<ast::Ast> {
     println!("[INFO]: compiling `{}`", file);
     println!("    [INFO]: lexing `{}`", file);
     let tokens = match lexer::lex(file) {
         Ok(tokens) => tokens,
-        Err(error) => {
-            println!("[ERROR] `{}`: {}", file, error.to_string());
-            process::exit(1);
+        Err(err) => {
+            diagnostics.push(format!("`{}`: {}", file, err).into());
+            return Vec::new();
         },
     };
 
     println!("    [INFO]: parsing `{}`\n", file);
-    let parsed = ast::Ast::parse(&tokens);
-    if let Err(error) = parsed {
-        println!("{}", error.to_string());
-        process::exit(1);
-    }
-
-    let parsed = parsed.unwrap();
+    let parsed = ast::Ast::parse_recovering(&tokens, diagnostics);
 
     // println!("\nAST: {:#?}\n", parsed);
 
+    parsed
+}
+
+fn check(file: &str, search: Vec<PathBuf>) -> Vec<ast::Ast> {
+    // every file is loaded before the errors of the files that didnt parse are reported
+    let mut diagnostics = diagnostic::Diagnostics::new();
+    let mut loader = module::Loader::new(search);
+    let mut parsed = match loader.load(file, &mut diagnostics) {
+        Some(parsed) => parsed,
+        None => {
+            let _ = diagnostics.finish(()).is_err_and(|err| error(err.as_ref()));
+            process::exit(1);
+        },
+    };
+
+    // the items that resolved are typechecked even when others didnt so every error is reported
+    let mut resolver = resolve::Resolver::new();
+    diagnostics.collect(resolver.resolve(&mut parsed, false));
+
     let mut typechecker = typecheck::TypeChecker::new();
-    let _ = typechecker.check(&parsed, false).is_err_and(|err| error_no_log(&err));
+    diagnostics.collect(typechecker.check(&parsed, false));
+    let _ = diagnostics.finish(()).is_err_and(|err| error(err.as_ref()));
 
-    return parsed;
+    parsed
 }
 
 
 fn main() {
     let args = cli();
+    let color = COLORS.iter().find(|color| args.flags.contains(&format!("--color={}", color)));
+    diagnostic::set_color(color.unwrap_or(&"auto"));
+    if let Some(limit) = args.values.get("--error-limit") {
+        match limit.parse::<usize>() {
+            Ok(limit) => diagnostic::set_limit(limit),
+            Err(_) => {
+                help();
+                process::exit(1);
+            },
+        }
+    }
     let file = match args.pos_arg.get(1) {
         Some(arg) => arg,
         None => {
@@ -97,30 +123,40 @@ fn main() {
         },
     };
 
-    let parsed = generate_ast(&file);
+    let search: Vec<PathBuf> = match args.values.get("-I") {
+        Some(dirs) => std::env::split_paths(dirs).collect(),
+        None => Vec::new(),
+    };
+    let mut parsed = check(file, search);
+
+    if args.flags.contains(&"-o".to_string()) {
+        println!("[INFO]: folding constant expressions");
+        fold::fold(&mut parsed);
+    }
 
     println!("[INFO]: generating linux-x86_64-fasm");
-    let mut codegen = match asm::CodeGen::new(&file) {
+    let mut codegen = match asm::CodeGen::new(file) {
         Ok(codegen) => codegen,
-        Err(error) => {
-            println!("[ERROR] `{}`: {}", file, error.to_string());
+        Err(err) => {
+            let err: Box<dyn std::error::Error> = format!("`{}`: {}", file, err).into();
+            error(err.as_ref());
             process::exit(1);
         },
     };
 
-    let _ = codegen.generate(&parsed, true).is_err_and(|err| error(&err));
+    let _ = codegen.generate(&parsed, true).is_err_and(|err| error(err.as_ref()));
 
     // flush the buffer
-    let _ = codegen.flush().is_err_and(|err| error(&err));
+    let _ = codegen.flush().is_err_and(|err| error(err.as_ref()));
 
     let output = codegen.assemble();
-    let _ = output.as_ref().is_err_and(|err| error(err));
+    let _ = output.as_ref().is_err_and(|err| error(err.as_ref()));
     println!("[FASM]:\n{}", output.unwrap());
 
     println!("[INFO]: compilation done");
 
     if args.flags.contains(&"-r".to_string()) {
-        let _ = codegen.run().is_err_and(|err| error_no_log(&err));
+        let _ = codegen.run().is_err_and(|err| error(err.as_ref()));
     }
 }
 
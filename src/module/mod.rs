@@ -0,0 +1,456 @@
+use crate::ast::{Ast, Type, Value};
+use crate::source::Loc;
+use crate::diagnostic::{Diagnostic, Diagnostics};
+use crate::generate_ast;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+// the items of a module are renamed to `a.b::name` so they cant collide with
+// the items of other modules once every module is joined into one ast
+#[derive(Clone)]
+struct Module {
+    prefix: String,
+    // name, public
+    items: HashMap<String, bool>,
+}
+
+impl Module {
+    fn mangle(&self, name: &str) -> String {
+        if self.prefix.is_empty() {
+            return name.to_string();
+        }
+        format!("{}::{}", self.prefix, name)
+    }
+}
+
+// what the names inside of one module refer to
+struct Namespace {
+    module: Module,
+    // alias, module
+    imports: HashMap<String, Module>,
+    // variables and parameters in scope, they shadow the items of the module
+    locals: Vec<String>,
+}
+
+impl Namespace {
+    // `module::name` is looked up in the imported module, other names in this module
+    fn rename(&self, name: &mut String, loc: Loc) -> Result<(), Box<dyn std::error::Error>> {
+        if let Some((alias, item)) = name.split_once("::") {
+            let module = match self.imports.get(alias) {
+                Some(module) => module,
+                None => {
+                    return Err(Diagnostic::error(loc, format!("unknown module `{}`", alias)).into());
+                },
+            };
+            match module.items.get(item) {
+                Some(true) => *name = module.mangle(item),
+                Some(false) => {
+                    return Err(Diagnostic::error(loc, format!("`{}` is private in module `{}`", item, module.prefix)).into());
+                },
+                None => {
+                    return Err(Diagnostic::error(loc, format!("module `{}` has no item `{}`", module.prefix, item)).into());
+                },
+            }
+        } else if self.module.items.contains_key(name) {
+            *name = self.module.mangle(name);
+        }
+        Ok(())
+    }
+
+    fn rewrite_type(&self, value_t: &mut Type) -> Result<(), Box<dyn std::error::Error>> {
+        match value_t {
+            Type::Struct(struct_t) => self.rename(&mut struct_t.name, struct_t.loc)?,
+            Type::TypedPtr(inner_t) | Type::Array(inner_t, _) => self.rewrite_type(inner_t)?,
+            Type::ConstArray(elem_t, name, loc) => {
+                self.rewrite_type(elem_t)?;
+                self.rename(name, *loc)?;
+            },
+            _ => {},
+        }
+        Ok(())
+    }
+
+    fn rewrite_value(&self, value: &mut Value) -> Result<(), Box<dyn std::error::Error>> {
+        match value {
+            Value::BinaryExpr {l_expr, r_expr, ..}
+            | Value::Comparison {l_expr, r_expr, ..}
+            | Value::Logical {l_expr, r_expr, ..}
+            | Value::Index {value: l_expr, index: r_expr, ..} => {
+                self.rewrite_value(l_expr)?;
+                self.rewrite_value(r_expr)?;
+            },
+            Value::Unary {value, ..} | Value::Field {value, ..} | Value::Load {value, ..} => {
+                self.rewrite_value(value)?;
+            },
+            Value::FunctionCall {loc, name, params} => {
+                self.rename(name, *loc)?;
+                for param in params.iter_mut() {
+                    self.rewrite_value(param)?;
+                }
+            },
+            Value::Cast {value, cast_t: value_t, ..} | Value::Deref {value, deref_t: value_t, ..} => {
+                self.rewrite_value(value)?;
+                self.rewrite_type(value_t)?;
+            },
+            Value::Variant {loc, enum_t: Type::Struct(struct_t), variant} if self.imports.contains_key(&struct_t.name) => {
+                // `module::name` of a constant or global
+                let mut name = format!("{}::{}", struct_t.name, variant);
+                self.rename(&mut name, *loc)?;
+                *value = Value::Ident {
+                    loc: *loc,
+                    name,
+                };
+            },
+            Value::Variant {enum_t, ..} => self.rewrite_type(enum_t)?,
+            Value::Ref {value, ..} => self.rewrite_value(value)?,
+            Value::Sizeof {size_t, ..} => self.rewrite_type(size_t)?,
+            Value::Ident {loc, name} => {
+                if !self.locals.contains(name) {
+                    self.rename(name, *loc)?;
+                }
+            },
+            Value::Int(_) | Value::Bool(_) | Value::Str(_) | Value::Null => {},
+        }
+        Ok(())
+    }
+
+    // a statement with a name that doesnt resolve is reported and left out like in the resolver
+    fn rewrite(&mut self, ast: &mut Vec<Ast>, nested: bool) -> Result<(), Box<dyn std::error::Error>> {
+        let mut diagnostics = Diagnostics::new();
+        let scope = self.locals.len();
+        let mut failed: Vec<usize> = Vec::new();
+        for (index, instruction) in ast.iter_mut().enumerate() {
+            let rewritten = self.statement(instruction, nested, &mut diagnostics);
+            if diagnostics.collect(rewritten).is_none() {
+                failed.push(index);
+            }
+        }
+        let mut index = 0;
+        ast.retain(|_| {
+            index += 1;
+            !failed.contains(&(index - 1))
+        });
+        self.locals.truncate(scope);
+        diagnostics.finish(())
+    }
+
+    fn statement(&mut self, instruction: &mut Ast, nested: bool, diagnostics: &mut Diagnostics) -> Result<(), Box<dyn std::error::Error>> {
+        match instruction {
+            Ast::Function {loc, name, param_t, variadic, return_t, body} => {
+                self.rename(name, *loc)?;
+                for (_, param_t) in param_t.iter_mut() {
+                    self.rewrite_type(param_t)?;
+                }
+                self.rewrite_type(return_t)?;
+                let params = self.locals.len();
+                self.locals.extend(param_t.iter().map(|(param_name, _)| param_name.clone()));
+                if let Some(variadic) = variadic {
+                    self.locals.push(variadic.clone());
+                }
+                diagnostics.collect(self.rewrite(body, true));
+                self.locals.truncate(params);
+            },
+            Ast::Struct {loc, name, fields} => {
+                self.rename(name, *loc)?;
+                for (_, field_t) in fields.iter_mut() {
+                    self.rewrite_type(field_t)?;
+                }
+            },
+            Ast::Enum {loc, name, variants} => {
+                self.rename(name, *loc)?;
+                for (_, value) in variants.iter_mut() {
+                    if let Some(value) = value {
+                        self.rewrite_value(value)?;
+                    }
+                }
+            },
+            Ast::Const {loc, name, const_t, value} => {
+                self.rename(name, *loc)?;
+                self.rewrite_type(const_t)?;
+                self.rewrite_value(value)?;
+            },
+            Ast::Variable {loc, name, var_t, value} => {
+                self.rewrite_type(var_t)?;
+                self.rewrite_value(value)?;
+                if nested {
+                    self.locals.push(name.clone());
+                } else {
+                    self.rename(name, *loc)?;
+                }
+            },
+            Ast::MutateVar {loc, name, value} => {
+                if !self.locals.contains(name) {
+                    self.rename(name, *loc)?;
+                }
+                self.rewrite_value(value)?;
+            },
+            Ast::MutatePtr {ptr_type, ptr, value, ..} => {
+                self.rewrite_type(ptr_type)?;
+                self.rewrite_value(ptr)?;
+                self.rewrite_value(value)?;
+            },
+            Ast::Assign {target, value, ..} | Ast::Compound {target, value, ..} => {
+                self.rewrite_value(target)?;
+                self.rewrite_value(value)?;
+            },
+            Ast::Return {value, ..} | Ast::Expr {value, ..} => self.rewrite_value(value)?,
+            // the bodies are rewritten even when the condition isnt so their errors are reported as well
+            Ast::If {condition, body, else_body, ..} => {
+                let rewritten = self.rewrite_value(condition);
+                diagnostics.collect(self.rewrite(body, true));
+                diagnostics.collect(self.rewrite(else_body, true));
+                rewritten?;
+            },
+            Ast::While {condition, body, step, ..} => {
+                let rewritten = self.rewrite_value(condition);
+                diagnostics.collect(self.rewrite(body, true));
+                diagnostics.collect(self.rewrite(step, true));
+                rewritten?;
+            },
+            Ast::Match {value, arms, default, ..} => {
+                let mut rewritten = self.rewrite_value(value);
+                for (patterns, body) in arms {
+                    for pattern in patterns {
+                        rewritten = rewritten.and(self.rewrite_value(pattern));
+                    }
+                    diagnostics.collect(self.rewrite(body, true));
+                }
+                if let Some(default) = default {
+                    diagnostics.collect(self.rewrite(default, true));
+                }
+                rewritten?;
+            },
+            Ast::Block {body, ..} => {
+                diagnostics.collect(self.rewrite(body, true));
+            },
+            Ast::Include {loc, ..} => {
+                return Err(Diagnostic::error(*loc, "includes need to be global").into());
+            },
+            Ast::Import {loc, ..} => {
+                return Err(Diagnostic::error(*loc, "imports need to be global").into());
+            },
+            Ast::Pub {loc} => {
+                return Err(Diagnostic::error(*loc, "`pub` can only be used on global declarations").into());
+            },
+            Ast::Break {..} | Ast::Continue {..} | Ast::InlineAsm {..} => {},
+        }
+        Ok(())
+    }
+}
+
+// `LANGIN_STD` or the std library installed next to the binary by `build.sh`
+fn std_dir() -> Option<PathBuf> {
+    if let Ok(dir) = std::env::var("LANGIN_STD") {
+        return Some(PathBuf::from(dir));
+    }
+    let installed = std::env::current_exe().ok()?.parent()?.join("../lib/langin/std");
+    Some(installed).filter(|dir| dir.is_dir())
+}
+
+// a import in a module, the file is found relative to the file the import is written in
+struct Import {
+    loc: Loc,
+    path: Vec<String>,
+    file: PathBuf,
+}
+
+// loads every module once and joins them into one ast where a module comes after the modules it imports
+pub struct Loader {
+    // searched after the directory of the including file
+    search: Vec<PathBuf>,
+    // paths starting with `std/` are also searched in here
+    std_dir: Option<PathBuf>,
+    modules: HashMap<PathBuf, Module>,
+    // files that are being loaded, importing one of them again is a cycle
+    loading: Vec<(PathBuf, String)>,
+    prefixes: Vec<String>,
+    ast: Vec<Ast>,
+    // set when a file couldnt be found or parsed
+    failed: bool,
+}
+
+impl Loader {
+    pub fn new(search: Vec<PathBuf>) -> Loader {
+        Loader {
+            search,
+            std_dir: std_dir(),
+            modules: HashMap::new(),
+            loading: Vec::new(),
+            prefixes: Vec::new(),
+            ast: Vec::new(),
+            failed: false,
+        }
+    }
+
+    // paths are relative to the including file, then to the search directories, the std library and the working directory
+    fn find(&self, path: &str, from: &Path) -> Option<PathBuf> {
+        let mut candidates: Vec<PathBuf> = vec![from.parent()?.join(path)];
+        candidates.extend(self.search.iter().map(|dir| dir.join(path)));
+        if let (Some(std_dir), Ok(std_path)) = (&self.std_dir, Path::new(path).strip_prefix("std")) {
+            candidates.push(std_dir.join(std_path));
+        }
+        candidates.push(PathBuf::from(path));
+        candidates.into_iter()
+            .find(|candidate| candidate.is_file())
+            .and_then(|candidate| std::fs::canonicalize(candidate).ok())
+    }
+
+    // parses the file and splices in the files it includes, a file is only included once per module.
+    // the errors are collected so the files after a bad one are still parsed
+    fn parse(&self, file: &Path, included: &mut Vec<PathBuf>, imports: &mut Vec<Import>, diagnostics: &mut Diagnostics) -> Vec<Ast> {
+        let mut ast: Vec<Ast> = Vec::new();
+        for instruction in generate_ast(&file.to_string_lossy(), diagnostics) {
+            match instruction {
+                Ast::Include {loc, path} => {
+                    let include = match self.find(&path, file) {
+                        Some(include) => include,
+                        None => {
+                            diagnostics.push(Diagnostic::error(loc, format!("cant find include `{}`", path)).into());
+                            continue;
+                        },
+                    };
+                    if !included.contains(&include) {
+                        included.push(include.clone());
+                        ast.extend(self.parse(&include, included, imports, diagnostics));
+                    }
+                },
+                Ast::Import {loc, ref path} => {
+                    let import = format!("{}.lang", path.join("/"));
+                    let import_file = match self.find(&import, file) {
+                        Some(import_file) => import_file,
+                        None => {
+                            diagnostics.push(Diagnostic::error(loc, format!("cant find module `{}`", import)).into());
+                            continue;
+                        },
+                    };
+                    imports.push(Import {
+                        loc,
+                        path: path.clone(),
+                        file: import_file,
+                    });
+                    ast.push(instruction);
+                },
+                _ => ast.push(instruction),
+            }
+        }
+        ast
+    }
+    // the items declared in the module, `pub` applies to the declaration after it
+    fn items(ast: &Vec<Ast>) -> Result<HashMap<String, bool>, Box<dyn std::error::Error>> {
+        let mut items: HashMap<String, bool> = HashMap::new();
+        let mut public: Option<Loc> = None;
+        for instruction in ast {
+            match (instruction, public) {
+                (Ast::Pub {loc}, None) => {
+                    public = Some(*loc);
+                    continue;
+                },
+                (Ast::Function {name, ..}, _)
+                | (Ast::Struct {name, ..}, _)
+                | (Ast::Enum {name, ..}, _)
+                | (Ast::Const {name, ..}, _)
+                | (Ast::Variable {name, ..}, _) => {
+                    items.insert(name.clone(), public.is_some());
+                },
+                (_, Some(loc)) => {
+                    return Err(Diagnostic::error(loc, "expected a declaration after `pub`").into());
+                },
+                _ => {},
+            }
+            public = None;
+        }
+        if let Some(loc) = public {
+            return Err(Diagnostic::error(loc, "expected a declaration after `pub`").into());
+        }
+        Ok(items)
+    }
+
+    // the prefix of the module, modules imported by the same path from different directories are numbered
+    fn prefix(&mut self, path: String) -> String {
+        let mut prefix = path.clone();
+        let mut count = 1;
+        while self.prefixes.contains(&prefix) {
+            count += 1;
+            prefix = format!("{}{}", path, count);
+        }
+        self.prefixes.push(prefix.clone());
+        prefix
+    }
+
+    fn module(&mut self, file: PathBuf, name: String, prefix: String, loc: Loc, diagnostics: &mut Diagnostics) -> Result<Module, Box<dyn std::error::Error>> {
+        if let Some(start) = self.loading.iter().position(|(path, _)| *path == file) {
+            let chain: Vec<String> = self.loading[start..].iter()
+                .map(|(_, loading)| format!("`{}`", loading))
+                .chain(std::iter::once(format!("`{}`", name)))
+                .collect();
+            return Err(Diagnostic::error(loc, format!("import cycle {}", chain.join(" -> "))).into());
+        } else if let Some(module) = self.modules.get(&file) {
+            return Ok(module.clone());
+        }
+        self.loading.push((file.clone(), name));
+
+        let mut included: Vec<PathBuf> = vec![file.clone()];
+        let mut imports: Vec<Import> = Vec::new();
+        let errors = diagnostics.errors.len();
+        let ast = self.parse(&file, &mut included, &mut imports, diagnostics);
+        let items = Self::items(&ast);
+        let mut namespace = Namespace {
+            module: Module {
+                prefix: self.prefix(prefix),
+                items: diagnostics.collect(items).unwrap_or_default(),
+            },
+            imports: HashMap::new(),
+            locals: Vec::new(),
+        };
+        self.failed |= diagnostics.errors.len() != errors;
+
+        // the imports are loaded even when this module didnt parse so their errors are reported as well
+        for import in imports {
+            let alias = import.path[import.path.len() - 1].clone();
+            let module = self.module(import.file, format!("{}.lang", import.path.join("/")), import.path.join("."), import.loc, diagnostics);
+            let Some(module) = diagnostics.collect(module) else {
+                self.failed = true;
+                continue;
+            };
+            if let Some(imported) = namespace.imports.get(&alias) {
+                if imported.prefix != module.prefix {
+                    diagnostics.push(Diagnostic::error(import.loc, format!("module `{}` conflicts with `{}`", module.prefix, imported.prefix)).into());
+                    self.failed = true;
+                    continue;
+                }
+            }
+            namespace.imports.insert(alias, module);
+        }
+
+        let mut items: Vec<Ast> = ast.into_iter()
+            .filter(|instruction| !matches!(instruction, Ast::Import {..} | Ast::Pub {..}))
+            .collect();
+        // a name could refer to a item that didnt parse, so the names are only rewritten while every file loaded
+        if !self.failed {
+            diagnostics.collect(namespace.rewrite(&mut items, false));
+        }
+
+        self.loading.pop();
+        self.modules.insert(file, namespace.module.clone());
+        self.ast.extend(items);
+        Ok(namespace.module)
+    }
+
+    // the ast of every module, `None` when a file couldnt be loaded or parsed
+    pub fn load(&mut self, file: &str, diagnostics: &mut Diagnostics) -> Option<Vec<Ast>> {
+        let main = match std::fs::canonicalize(file) {
+            Ok(main) if main.is_file() => main,
+            _ => {
+                diagnostics.push(format!("cant find `{}`", file).into());
+                return None;
+            },
+        };
+        let loaded = self.module(main, file.to_string(), String::new(), Loc::default(), diagnostics);
+        diagnostics.collect(loaded)?;
+        if self.failed {
+            return None;
+        }
+        Some(std::mem::take(&mut self.ast))
+    }
+}
+
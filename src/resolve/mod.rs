@@ -0,0 +1,351 @@
+use crate::ast::{Ast, Enum, Struct, Type, Value};
+use crate::source::Loc;
+use crate::diagnostic::{Diagnostic, Diagnostics};
+use crate::fold;
+use std::collections::HashMap;
+
+// struct types are parsed by name only, the resolver fills in their fields
+// so the typechecker and the codegen can compute sizes and offsets.
+// constants are replaced by their value
+pub struct Resolver {
+    structs: HashMap<String, Struct>,
+    enums: HashMap<String, Enum>,
+    constants: HashMap<String, Value>,
+    // where each type and constant was declared, by kind and name
+    defined: HashMap<(&'static str, String), Loc>,
+}
+
+
+impl Resolver {
+    pub fn new() -> Resolver {
+        Resolver {
+            structs: HashMap::new(),
+            enums: HashMap::new(),
+            constants: HashMap::new(),
+            defined: HashMap::new(),
+        }
+    }
+
+    fn resolve_type(&self, value_t: &mut Type, loc: Loc) -> Result<(), Box<dyn std::error::Error>> {
+        match value_t {
+            Type::Struct(struct_t) => match self.named_type(&struct_t.name) {
+                Some(resolved) => *value_t = resolved,
+                None => {
+                    return Err(Diagnostic::error(struct_t.loc, format!("unknown type `{}`", struct_t.name)).into());
+                },
+            },
+            Type::Array(elem_t, len) => {
+                self.resolve_type(elem_t, loc)?;
+                if **elem_t == Type::Void || *len == 0 {
+                    return Err(Diagnostic::error(loc, "arrays need a non `Void` element type and a length bigger than 0").into());
+                }
+            },
+            Type::TypedPtr(pointee_t) => {
+                self.resolve_type(pointee_t, loc)?;
+                if **pointee_t == Type::Void {
+                    return Err(Diagnostic::error(loc, "pointers cant point to `Void`").help("use `ptr` instead").into());
+                }
+            },
+            Type::ConstArray(elem_t, name, name_loc) => {
+                let len = match self.constants.get(name) {
+                    Some(Value::Int(len)) => *len,
+                    Some(_) => {
+                        return Err(Diagnostic::error(*name_loc, format!("constant `{}` cant be used as array length", name)).into());
+                    },
+                    None => {
+                        return Err(Diagnostic::error(*name_loc, format!("unknown constant `{}`", name)).into());
+                    },
+                };
+                *value_t = Type::Array(elem_t.clone(), len);
+                self.resolve_type(value_t, loc)?;
+            },
+            _ => {},
+        }
+        Ok(())
+    }
+
+    fn named_type(&self, name: &String) -> Option<Type> {
+        if let Some(struct_t) = self.structs.get(name) {
+            return Some(Type::Struct(struct_t.clone()));
+        }
+        self.enums.get(name).map(|enum_t| Type::Enum(enum_t.clone()))
+    }
+
+    fn resolve_value(&self, value: &mut Value) -> Result<(), Box<dyn std::error::Error>> {
+        match value {
+            Value::BinaryExpr {l_expr, r_expr, ..}
+            | Value::Comparison {l_expr, r_expr, ..}
+            | Value::Logical {l_expr, r_expr, ..} => {
+                self.resolve_value(l_expr)?;
+                self.resolve_value(r_expr)?;
+            },
+            Value::Unary {value, ..} | Value::Field {value, ..} | Value::Load {value, ..} => {
+                self.resolve_value(value)?;
+            },
+            Value::Index {loc, value: base, index} => {
+                self.resolve_value(base)?;
+                self.resolve_value(index)?;
+                if let Value::Ident {name, ..} = base.as_ref() {
+                    if let Some(deref_t) = self.named_type(name) {
+                        // `Name[ptr]` dereferences a pointer to the struct or enum
+                        let ptr = std::mem::replace(index.as_mut(), Value::Null);
+                        *value = Value::Deref {
+                            loc: *loc,
+                            value: Box::new(ptr),
+                            deref_t,
+                        };
+                    }
+                }
+            },
+            Value::FunctionCall {loc, name, params} => {
+                for param in params.iter_mut() {
+                    self.resolve_value(param)?;
+                }
+                if let (Some(cast_t), 1) = (self.named_type(name), params.len()) {
+                    // `Name(value)` casts to the struct or enum
+                    let cast_value = std::mem::replace(&mut params[0], Value::Null);
+                    *value = Value::Cast {
+                        loc: *loc,
+                        value: Box::new(cast_value),
+                        cast_t,
+                    };
+                }
+            },
+            Value::Cast {loc, value, cast_t: value_t} | Value::Deref {loc, value, deref_t: value_t} => {
+                self.resolve_value(value)?;
+                self.resolve_type(value_t, *loc)?;
+            },
+            Value::Variant {loc, enum_t, variant} => {
+                self.resolve_type(enum_t, *loc)?;
+                match enum_t {
+                    Type::Enum(resolved) if resolved.variant(variant).is_none() => {
+                        return Err(Diagnostic::error(*loc, format!("enum `{}` has no variant `{}`", resolved.name, variant)).into());
+                    },
+                    Type::Enum(_) => {},
+                    _ => {
+                        return Err(Diagnostic::error(*loc, format!("`{:?}` is not a enum", enum_t)).into());
+                    },
+                }
+            },
+            Value::Ref {value, ..} => self.resolve_value(value)?,
+            Value::Sizeof {loc, size_t} => self.resolve_type(size_t, *loc)?,
+            Value::Ident {name, ..} => {
+                if let Some(constant) = self.constants.get(name) {
+                    *value = constant.clone();
+                }
+            },
+            Value::Int(_) | Value::Bool(_) | Value::Str(_) | Value::Null => {},
+        }
+        Ok(())
+    }
+
+    // fields can only use structs declared before them so a struct cant contain itself
+    fn redefined(&self, loc: Loc, kind: &'static str, name: &String) -> Box<dyn std::error::Error> {
+        let mut diagnostic = Diagnostic::error(loc, format!("{} `{}` already exists", kind, name));
+        if let Some(defined) = self.defined.get(&(kind, name.clone())) {
+            diagnostic = diagnostic.label(*defined, "previously defined here");
+        }
+        diagnostic.into()
+    }
+
+    fn declare(&mut self, loc: Loc, name: &String, fields: &mut [(String, Type)]) -> Result<(), Box<dyn std::error::Error>> {
+        if self.named_type(name).is_some() {
+            return Err(self.redefined(loc, "type", name));
+        }
+        for (field_name, field_t) in fields.iter_mut() {
+            self.resolve_type(field_t, loc)?;
+            if *field_t == Type::Void {
+                return Err(Diagnostic::error(loc, format!("field `{}` cant be of type `Void`", field_name)).into());
+            }
+        }
+        for (index, (field_name, _)) in fields.iter().enumerate() {
+            if fields[..index].iter().any(|field| field.0 == *field_name) {
+                return Err(Diagnostic::error(loc, format!("field `{}` already exists in struct `{}`", field_name, name)).into());
+            }
+        }
+        self.defined.insert(("type", name.clone()), loc);
+        self.structs.insert(name.clone(), Struct {
+            name: name.clone(),
+            fields: fields.to_owned(),
+            loc,
+        });
+        Ok(())
+    }
+
+    // discriminants can only use constants declared before the enum
+    fn declare_enum(&mut self, loc: Loc, name: &String, variants: &mut [(String, Option<Value>)]) -> Result<(), Box<dyn std::error::Error>> {
+        if self.named_type(name).is_some() {
+            return Err(self.redefined(loc, "type", name));
+        }
+        let mut resolved: Vec<(String, usize)> = Vec::new();
+        let mut next = 0;
+        for (variant, value) in variants.iter_mut() {
+            let discriminant = match value {
+                Some(value) => {
+                    self.resolve_value(value)?;
+                    match fold::evaluate(value) {
+                        Some(discriminant) if !fold::is_signed(value) => discriminant,
+                        _ => {
+                            return Err(Diagnostic::error(loc, format!("discriminant of `{}` needs a positive value known at compile time", variant)).into());
+                        },
+                    }
+                },
+                None => next,
+            };
+            if let Some(used) = resolved.iter().find(|other| other.0 == *variant || other.1 == discriminant) {
+                return Err(Diagnostic::error(loc, format!("variant `{}` conflicts with `{}` in enum `{}`", variant, used.0, name)).into());
+            }
+            resolved.push((variant.clone(), discriminant));
+            next = discriminant.wrapping_add(1);
+        }
+        self.defined.insert(("type", name.clone()), loc);
+        self.enums.insert(name.clone(), Enum {
+            name: name.clone(),
+            variants: resolved,
+        });
+        Ok(())
+    }
+
+    // constants can only use constants declared before them
+    fn define(&mut self, loc: Loc, name: &String, const_t: &mut Type, value: &mut Value) -> Result<(), Box<dyn std::error::Error>> {
+        if self.constants.contains_key(name) {
+            return Err(self.redefined(loc, "constant", name));
+        }
+        self.defined.insert(("constant", name.clone()), loc);
+        self.resolve_type(const_t, loc)?;
+        self.resolve_value(value)?;
+        if !matches!(value, Value::Bool(_) | Value::Str(_) | Value::Variant {..}) {
+            match fold::literal(value, loc) {
+                Some(literal) => *value = literal,
+                None => {
+                    return Err(Diagnostic::error(loc, format!("constant `{}` needs a value known at compile time", name)).into());
+                },
+            }
+        }
+        self.constants.insert(name.clone(), value.clone());
+        Ok(())
+    }
+
+    fn constant_check(&self, name: &String, loc: Loc) -> Result<(), Box<dyn std::error::Error>> {
+        if self.constants.contains_key(name) {
+            return Err(Diagnostic::error(loc, format!("`{}` is a constant", name)).into());
+        }
+        Ok(())
+    }
+
+    // a statement that doesnt resolve is reported and left out, so the typechecker can still check the ones that did
+    pub fn resolve(&mut self, ast: &mut Vec<Ast>, nested: bool) -> Result<(), Box<dyn std::error::Error>> {
+        let mut diagnostics = Diagnostics::new();
+        let mut failed: Vec<usize> = Vec::new();
+        if !nested {
+            // structs and constants are declared first so functions can use them before their declaration
+            for (index, instruction) in ast.iter_mut().enumerate() {
+                let declared = match instruction {
+                    Ast::Struct {loc, name, fields} => self.declare(*loc, name, fields),
+                    Ast::Const {loc, name, const_t, value} => self.define(*loc, name, const_t, value),
+                    Ast::Enum {loc, name, variants} => self.declare_enum(*loc, name, variants),
+                    _ => Ok(()),
+                };
+                if diagnostics.collect(declared).is_none() {
+                    failed.push(index);
+                }
+            }
+        }
+
+        for (index, instruction) in ast.iter_mut().enumerate() {
+            if failed.contains(&index) {
+                continue;
+            }
+            let resolved = self.statement(instruction, nested, &mut diagnostics);
+            if diagnostics.collect(resolved).is_none() {
+                failed.push(index);
+            }
+        }
+        let mut index = 0;
+        ast.retain(|_| {
+            index += 1;
+            !failed.contains(&(index - 1))
+        });
+        diagnostics.finish(())
+    }
+
+    fn statement(&mut self, instruction: &mut Ast, nested: bool, diagnostics: &mut Diagnostics) -> Result<(), Box<dyn std::error::Error>> {
+        match instruction {
+            Ast::Struct {loc, ..} => {
+                if nested {
+                    return Err(Diagnostic::error(*loc, "structs need to be global").into());
+                }
+            },
+            Ast::Const {loc, ..} => {
+                if nested {
+                    return Err(Diagnostic::error(*loc, "constants need to be global").into());
+                }
+            },
+            Ast::Enum {loc, ..} => {
+                if nested {
+                    return Err(Diagnostic::error(*loc, "enums need to be global").into());
+                }
+            },
+            Ast::Function {loc, param_t, variadic, return_t, body, ..} => {
+                for (var_name, var_t) in param_t.iter_mut() {
+                    self.constant_check(var_name, *loc)?;
+                    self.resolve_type(var_t, *loc)?;
+                }
+                if let Some(variadic) = variadic {
+                    self.constant_check(variadic, *loc)?;
+                }
+                self.resolve_type(return_t, *loc)?;
+                // the signature resolved so calls to the function can still be checked without the statements that didnt
+                diagnostics.collect(self.resolve(body, true));
+            },
+            Ast::Variable {loc, name, var_t, value} => {
+                self.constant_check(name, *loc)?;
+                self.resolve_type(var_t, *loc)?;
+                self.resolve_value(value)?;
+            },
+            Ast::MutatePtr {loc, ptr_type, ptr, value} => {
+                self.resolve_type(ptr_type, *loc)?;
+                self.resolve_value(ptr)?;
+                self.resolve_value(value)?;
+            },
+            Ast::Assign {target, value, ..} | Ast::Compound {target, value, ..} => {
+                self.resolve_value(target)?;
+                self.resolve_value(value)?;
+            },
+            Ast::MutateVar {loc, name, value} => {
+                self.constant_check(name, *loc)?;
+                self.resolve_value(value)?;
+            },
+            Ast::Return {value, ..} | Ast::Expr {value, ..} => {
+                self.resolve_value(value)?;
+            },
+            Ast::If {condition, body, else_body, ..} => {
+                diagnostics.collect(self.resolve_value(condition));
+                diagnostics.collect(self.resolve(body, true));
+                diagnostics.collect(self.resolve(else_body, true));
+            },
+            Ast::While {condition, body, step, ..} => {
+                diagnostics.collect(self.resolve_value(condition));
+                diagnostics.collect(self.resolve(body, true));
+                diagnostics.collect(self.resolve(step, true));
+            },
+            Ast::Match {value, arms, default, ..} => {
+                diagnostics.collect(self.resolve_value(value));
+                for (patterns, body) in arms {
+                    for pattern in patterns {
+                        diagnostics.collect(self.resolve_value(pattern));
+                    }
+                    diagnostics.collect(self.resolve(body, true));
+                }
+                if let Some(default) = default {
+                    diagnostics.collect(self.resolve(default, true));
+                }
+            },
+            Ast::Block {body, ..} => {
+                diagnostics.collect(self.resolve(body, true));
+            },
+            Ast::Break {..} | Ast::Continue {..} | Ast::InlineAsm {..} | Ast::Include {..} | Ast::Import {..} | Ast::Pub {..} => {},
+        }
+        Ok(())
+    }
+}
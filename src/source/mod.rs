@@ -0,0 +1,95 @@
+use lib_lexin::Token;
+use std::sync::Mutex;
+
+// every file that is lexed is added to the source map, locations refer to it by id
+static SOURCES: Mutex<Vec<Source>> = Mutex::new(Vec::new());
+
+// lib_lexin locations are only a line and column, the file id is stored in the
+// upper bits of the line and the length of the token in the upper bits of the
+// column until the parser turns them into a `Loc`
+const SHIFT: u32 = 32;
+const MASK: usize = (1 << SHIFT) - 1;
+
+#[derive(Debug, Clone)]
+pub struct Source {
+    pub path: String,
+    pub text: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Loc {
+    pub file: usize,
+    pub line: usize,
+    pub col: usize,
+    // number of characters the location covers
+    pub len: usize,
+}
+
+impl Loc {
+    pub fn of(token: &Token) -> Loc {
+        let (line, col) = token.loc();
+        Loc {
+            file: line >> SHIFT,
+            line: line & MASK,
+            col: col & MASK,
+            len: col >> SHIFT,
+        }
+    }
+
+    // the location from the start of this one to the end of the other, when both are on the same line
+    pub fn to(&self, end: Loc) -> Loc {
+        if self.file != end.file || self.line != end.line || end.col < self.col {
+            return *self;
+        }
+        Loc {
+            len: end.col + end.len - self.col,
+            ..*self
+        }
+    }
+
+    pub fn path(&self) -> String {
+        match source(self.file) {
+            Some(source) => source.path,
+            None => String::from("<unknown>"),
+        }
+    }
+
+    // the text of the line the location is on
+    pub fn text(&self) -> Option<String> {
+        let source = source(self.file)?;
+        source.text.lines().nth(self.line.checked_sub(1)?).map(|line| line.to_string())
+    }
+
+    // the text the location covers
+    pub fn slice(&self) -> String {
+        let text = self.text().unwrap_or_default();
+        let start = self.col.saturating_sub(1).min(text.len());
+        text.get(start..(start + self.len).min(text.len())).unwrap_or_default().to_string()
+    }
+}
+
+pub fn add(path: &str, text: &str) -> usize {
+    let mut sources = SOURCES.lock().unwrap();
+    sources.push(Source {
+        path: path.to_string(),
+        text: text.to_string(),
+    });
+    sources.len() - 1
+}
+
+pub fn source(file: usize) -> Option<Source> {
+    return SOURCES.lock().unwrap().get(file).cloned();
+}
+
+// attaches the file id and the span to the location of the token
+pub fn attach(token: Token, file: usize, (line, col, len): (usize, usize, usize)) -> Token {
+    let loc = (line | (file << SHIFT), col | (len << SHIFT));
+    match token {
+        Token::Keyword(name, _) => Token::Keyword(name, loc),
+        Token::Section(name, text, _) => Token::Section(name, text, loc),
+        Token::Integer(integer, _) => Token::Integer(integer, loc),
+        Token::Float(float, _) => Token::Float(float, loc),
+        Token::Symbol(character, name, _) => Token::Symbol(character, name, loc),
+        Token::Ident(name, _) => Token::Ident(name, loc),
+    }
+}
@@ -1,155 +1,527 @@
-use crate::ast::{Ast, Type, Value, Comparison};
-use crate::log_color;
+use crate::ast::{Ast, Operator, Type, UnaryOp, Value};
+use crate::source::Loc;
+use crate::diagnostic::{Diagnostic, Diagnostics};
+use crate::fold;
 use std::collections::HashMap;
 
+// return type, parameter types, variadic
+type Signature = (Type, Vec<(String, Type)>, bool);
+
 pub struct TypeChecker {
     current_fn: String,
-    // name, (return type, parameter types)
-    functions: HashMap<String, (Type, Vec<(String, Type)>)>,
+    functions: HashMap<String, Signature>,
+    // where each function was defined
+    defined: HashMap<String, Loc>,
     variables: HashMap<String, Type>,
+    // labels of the enclosing loops
+    loops: Vec<Option<String>>,
 }
 
 
 impl TypeChecker {
     pub fn new() -> TypeChecker {
-        return TypeChecker {
+        TypeChecker {
             current_fn: String::new(),
             functions: HashMap::new(),
+            defined: HashMap::new(),
             variables: HashMap::new(),
-        };
+            loops: Vec::new(),
+        }
     }
 
-    fn value_type(&self, value: &Value, loc: (usize, usize)) -> Result<Type, Box<dyn std::error::Error>> {
-        return match value {
+    fn value_type(&self, value: &Value) -> Result<Type, Box<dyn std::error::Error>> {
+        match value {
+            Value::BinaryExpr {loc, l_expr, r_expr, op: op @ (Operator::ShiftLeft | Operator::ShiftRight)} => {
+                // the result of a shift has the type of the shifted value
+                let val_type = self.value_type(l_expr)?;
+                if !val_type.is_integer() {
+                    return Err(Diagnostic::error(*loc, format!("`{:?}` can only be applied to integers but got `{:?}`", op, val_type)).into());
+                }
+                self.operand_check(r_expr, &val_type, op, *loc)?;
+                Ok(val_type)
+            },
             Value::BinaryExpr {loc, l_expr, r_expr, op} => {
-                if self.value_type(&l_expr, loc.clone())? != self.value_type(&r_expr, loc.clone())?
-                || self.value_type(&r_expr, loc.clone())? != Type::Int
-                || self.value_type(&l_expr, loc.clone())? != Type::Int
-                {
-                    return Err(format!("{} binary expressions can only be applied to integers `{:?}` `{:?}` `{:?}`", log_color(*loc), *l_expr, op, *r_expr).into());
+                let l_type = self.value_type(l_expr)?;
+                if l_type.is_pointer() {
+                    // offsets are scaled by the size of the pointee, the difference of two pointers is counted in elements
+                    let r_type = self.value_type(r_expr)?;
+                    return match op {
+                        Operator::Plus | Operator::Minus if r_type.is_integer() => Ok(l_type),
+                        Operator::Minus if r_type == l_type => Ok(Type::Int),
+                        _ => Err(Diagnostic::error(*loc, format!("cant apply `{:?}` to `{:?}` and `{:?}`", op, l_type, r_type)).into()),
+                    };
+                }
+                let val_type = self.common_type(l_expr, r_expr, *loc)?;
+                if !val_type.is_integer() {
+                    return Err(Diagnostic::error(*loc, format!("binary expressions can only be applied to integers `{:?}` `{:?}` `{:?}`", *l_expr, op, *r_expr)).into());
+                }
+                Ok(val_type)
+            },
+            Value::Comparison {loc, l_expr, r_expr, ..} => {
+                let val_type = self.common_type(l_expr, r_expr, *loc)?;
+                if val_type.is_aggregate() {
+                    return Err(Diagnostic::error(*loc, format!("cant compare values of type `{:?}`", val_type)).into());
+                }
+                Ok(Type::Bool)
+            },
+            Value::Logical {loc, l_expr, r_expr, op} => {
+                for value in [l_expr, r_expr] {
+                    let val_type = self.value_type(value)?;
+                    if val_type != Type::Bool {
+                        return Err(Diagnostic::error(*loc, format!("`{:?}` expected `Bool` but got `{:?}`", op, val_type)).into());
+                    }
+                }
+                Ok(Type::Bool)
+            },
+            Value::Unary {loc, value, op: UnaryOp::Neg} => {
+                let val_type = self.value_type(value)?;
+                if value.is_literal() {
+                    // negative literals are signed until they are used with another type
+                    return Ok(Type::I64);
+                } else if !val_type.is_signed() {
+                    return Err(Diagnostic::error(*loc, format!("cant negate unsigned type `{:?}` without a cast", val_type)).into());
+                }
+                Ok(val_type)
+            },
+            Value::Unary {loc, value, op: UnaryOp::BitNot} => {
+                let val_type = self.value_type(value)?;
+                if !val_type.is_integer() {
+                    return Err(Diagnostic::error(*loc, format!("`BitNot` expected integer but got `{:?}`", val_type)).into());
+                }
+                Ok(val_type)
+            },
+            Value::Unary {loc, value, op} => {
+                let val_type = self.value_type(value)?;
+                if val_type != Type::Bool {
+                    return Err(Diagnostic::error(*loc, format!("`{:?}` expected `Bool` but got `{:?}`", op, val_type)).into());
                 }
-                Ok(Type::Int)
+                Ok(Type::Bool)
             },
             Value::FunctionCall {loc, name, params} => {
                 let function = match self.functions.get(name) {
                     Some(func) => func,
                     None => {
-                        return Err(format!("{} unknown function `{:?}`", log_color(*loc), name).into());
+                        return Err(Diagnostic::error(*loc, format!("unknown function `{:?}`", name)).into());
                     },
                 };
-                if params.len() != function.1.len() {
-                    return Err(format!("{} expected `{}` parameter(s) but got `{}`", log_color(*loc), function.1.len(), params.len()).into());
+                let expected = if function.2 && params.len() < function.1.len() {
+                    format!("at least `{}`", function.1.len())
+                } else if !function.2 && params.len() != function.1.len() {
+                    format!("`{}`", function.1.len())
+                } else {
+                    String::new()
+                };
+                if !expected.is_empty() {
+                    let mut diagnostic = Diagnostic::error(*loc, format!("expected {} parameter(s) but got `{}`", expected, params.len()));
+                    if let Some(defined) = self.defined.get(name) {
+                        diagnostic = diagnostic.label(*defined, format!("function `{}` defined here", name));
+                    }
+                    return Err(diagnostic.into());
                 }
                 for (index, parameter) in params.iter().enumerate() {
-                    let val_type = self.value_type(parameter, loc.clone())?;
-                    if val_type != function.1[index].1 {
-                        return Err(format!("{} expected `{:?}` but got `{:?}`", log_color(*loc), function.1[index].1, val_type).into());
+                    if let Some((_, param_t)) = function.1.get(index) {
+                        self.expect_type(parameter, param_t, *loc)?;
+                        continue;
+                    }
+                    // extra arguments are passed as 8 byte values
+                    let val_type = self.value_type(parameter)?;
+                    if val_type == Type::Void || val_type.is_aggregate() {
+                        return Err(Diagnostic::error(*loc, format!("cant pass `{:?}` as a variadic argument", val_type)).help("use a pointer instead").into());
                     }
                 }
                 Ok(function.0.clone())
             },
-            Value::Ident(ident) => {
-                if let Some(value_t) = self.variables.get(ident) {
+            Value::Ident {loc, name} => {
+                if let Some(value_t) = self.variables.get(name) {
                     Ok(value_t.clone())
                 } else {
-                    Err(format!("{} unknown identifier `{}`", log_color(loc), ident).into())
+                    Err(Diagnostic::error(*loc, format!("unknown identifier `{}`", name)).into())
                 }
             },
-            Value::Deref(value, deref_type) => {
-                let val_type = self.value_type(value, loc)?;
-                if val_type != Type::Ptr {
-                    return Err(format!("{} cant dereference non pointer type `{:?}`", log_color(loc), val_type).into());
+            Value::Deref {loc, value, deref_t} => {
+                let val_type = self.value_type(value)?;
+                if !val_type.is_pointer() {
+                    return Err(Diagnostic::error(*loc, format!("cant dereference non pointer type `{:?}`", val_type)).into());
                 }
-                Ok(deref_type.clone())
+                Ok(deref_t.clone())
             },
-            Value::Cast(_, cast_type) => Ok(cast_type.clone()),
-            Value::Ref(_) => Ok(Type::Ptr),
-            Value::Str(_) => Ok(Type::Ptr),
+            Value::Load {loc, value} => match self.value_type(value)? {
+                Type::TypedPtr(pointee_t) => Ok(*pointee_t),
+                Type::Ptr => Err(Diagnostic::error(*loc, "cant dereference `Ptr` with `*`").help("use a typed pointer or `type[ptr]`").into()),
+                val_type => Err(Diagnostic::error(*loc, format!("cant dereference non pointer type `{:?}`", val_type)).into()),
+            },
+            Value::Field {loc, value, field} => {
+                let val_type = self.value_type(value)?;
+                let struct_t = match &val_type {
+                    Type::Struct(struct_t) => struct_t,
+                    _ => {
+                        return Err(Diagnostic::error(*loc, format!("cant access field `{}` of non struct type `{:?}`", field, val_type)).into());
+                    },
+                };
+                match struct_t.field(field) {
+                    Some((_, field_t)) => Ok(field_t),
+                    None => Err(Diagnostic::error(*loc, format!("struct `{}` has no field `{}`", struct_t.name, field)).into()),
+                }
+            },
+            Value::Index {loc, value, index} => {
+                let val_type = self.value_type(value)?;
+                // pointers have no length to check the index against
+                let (elem_t, len) = match &val_type {
+                    Type::Array(elem_t, len) => (elem_t, Some(len)),
+                    Type::TypedPtr(pointee_t) => (pointee_t, None),
+                    _ => {
+                        return Err(Diagnostic::error(*loc, format!("cant index non array type `{:?}`", val_type)).into());
+                    },
+                };
+                let index_t = self.value_type(index)?;
+                if !index_t.is_integer() {
+                    return Err(Diagnostic::error(*loc, format!("expected integer index but got `{:?}`", index_t)).into());
+                } else if let (Some(integer), Some(len)) = (index.is_literal().then(|| fold::evaluate(index)).flatten(), len) {
+                    // negative indices wrap around to a value that is never in range
+                    if integer >= *len {
+                        let integer = if fold::is_signed(index) { (integer as i64).to_string() } else { integer.to_string() };
+                        return Err(Diagnostic::error(*loc, format!("index `{}` is out of range for `{:?}`", integer, val_type)).note(format!("the array has `{}` element(s)", len)).into());
+                    }
+                }
+                Ok(*elem_t.clone())
+            },
+            Value::Cast {loc, value, cast_t: cast_type} => {
+                let val_type = self.value_type(value)?;
+                // arrays decay to a pointer so they can be cast like one
+                if let (Type::Struct(_), _) | (_, Type::Struct(_) | Type::Array(..)) = (&val_type, cast_type) {
+                    return Err(Diagnostic::error(*loc, format!("cant cast `{:?}` to `{:?}`", val_type, cast_type)).into());
+                } else if let (Type::Enum(_), _) | (_, Type::Enum(_)) = (&val_type, cast_type) {
+                    // enums only convert to and from integers
+                    if val_type != *cast_type && !val_type.is_integer() && !cast_type.is_integer() {
+                        return Err(Diagnostic::error(*loc, format!("cant cast `{:?}` to `{:?}`", val_type, cast_type)).into());
+                    }
+                }
+                Ok(cast_type.clone())
+            },
+            Value::Ref {loc, value} => {
+                self.lvalue_check(value, *loc)?;
+                Ok(Type::TypedPtr(Box::new(self.value_type(value)?)))
+            },
+            Value::Sizeof {..} => Ok(Type::Int),
+            Value::Variant {enum_t, ..} => Ok(enum_t.clone()),
+            Value::Str(_) => Ok(Type::TypedPtr(Box::new(Type::U8))),
             Value::Int(_) => Ok(Type::Int),
+            Value::Bool(_) => Ok(Type::Bool),
             Value::Null => Ok(Type::Void),
+        }
+    }
+
+    // the shift count can be any integer, other operands have to fit in the type of the result
+    fn operand_check(&self, value: &Value, val_type: &Type, op: &Operator, loc: Loc) -> Result<(), Box<dyn std::error::Error>> {
+        if let Operator::ShiftLeft | Operator::ShiftRight = op {
+            let shift_t = self.value_type(value)?;
+            if !shift_t.is_integer() {
+                return Err(Diagnostic::error(loc, format!("expected integer shift count but got `{:?}`", shift_t)).into());
+            }
+            return Ok(());
+        }
+        self.expect_type(value, val_type, loc)
+    }
+
+    // only variables, dereferences and fields have an address
+    fn lvalue_check(&self, value: &Value, loc: Loc) -> Result<(), Box<dyn std::error::Error>> {
+        match value {
+            Value::Ident {..} | Value::Deref {..} | Value::Load {..} | Value::Field {..} | Value::Index {..} => Ok(()),
+            _ => Err(Diagnostic::error(loc, "expected a variable, dereference, field or index").into()),
+        }
+    }
+
+    // the value a match pattern compares against, it has to be known at compile time and used only once
+    fn pattern_check(&self, pattern: &Value, val_type: &Type, cases: &[usize], loc: Loc) -> Result<usize, Box<dyn std::error::Error>> {
+        self.expect_type(pattern, val_type, loc)?;
+        let case = match fold::evaluate(pattern) {
+            Some(case) if pattern.is_literal() || matches!(pattern, Value::Variant {..}) => fold::truncate(case, val_type),
+            _ => {
+                return Err(Diagnostic::error(loc, "match patterns need a value known at compile time").into());
+            },
         };
+        if cases.contains(&case) {
+            let case = if val_type.is_signed() { format!("{}", case as i64) } else { format!("{}", case) };
+            return Err(Diagnostic::error(loc, format!("duplicate match arm `{}`", case)).into());
+        }
+        Ok(case)
+    }
+
+    // checks that the value can be used where `expected` is required
+    fn expect_type(&self, value: &Value, expected: &Type, loc: Loc) -> Result<(), Box<dyn std::error::Error>> {
+        let val_type = self.value_type(value)?;
+        if expected.is_aggregate() {
+            return Err(Diagnostic::error(loc, format!("values of type `{:?}` cant be copied", expected)).help("use a pointer instead").into());
+        } else if let (Type::Array(..), Type::Ptr) = (&val_type, expected) {
+            // arrays decay to a pointer to their first element
+            return Ok(());
+        } else if let (Type::Array(elem_t, _), Type::TypedPtr(pointee_t)) = (&val_type, expected) {
+            if elem_t == pointee_t {
+                return Ok(());
+            }
+        }
+        if value.is_literal() && expected.is_integer() {
+            let signed = fold::is_signed(value);
+            if signed && !expected.is_signed() {
+                return Err(Diagnostic::error(loc, format!("cant negate unsigned type `{:?}` without a cast", expected)).into());
+            }
+            // constant expressions are folded so the result is checked and not just the literals
+            let Some(integer) = fold::evaluate(value) else {
+                return Ok(());
+            };
+            // a value fits when wrapping it around to the type doesnt change it, a bitwise not
+            // only flips the bits of the type so `~0` is the biggest `u8`
+            let fits = fold::truncate(integer, expected) == integer
+                || (matches!(value, Value::Unary {op: UnaryOp::BitNot, ..}) && fold::truncate(!integer, expected) == !integer);
+            if !fits {
+                let integer = if signed || expected.is_signed() { (integer as i64).to_string() } else { integer.to_string() };
+                return Err(Diagnostic::error(loc, format!("integer `{}` doesnt fit in `{:?}`", integer, expected)).into());
+            }
+            return Ok(());
+        } else if !val_type.widens_to(expected) {
+            return Err(Diagnostic::error(loc, format!("expected `{:?}` but got `{:?}`", expected, val_type)).into());
+        }
+        Ok(())
+    }
+
+    // integer literals take the type of the other side, other integers are widened to the bigger type
+    fn common_type(&self, l_expr: &Value, r_expr: &Value, loc: Loc) -> Result<Type, Box<dyn std::error::Error>> {
+        let l_type = self.value_type(l_expr)?;
+        let r_type = self.value_type(r_expr)?;
+        if l_expr.is_literal() && r_expr.is_literal() {
+            return Ok(if l_type.is_signed() || r_type.is_signed() { Type::I64 } else { Type::Int });
+        } else if l_expr.is_literal() && r_type.is_integer() {
+            self.expect_type(l_expr, &r_type, loc)?;
+            return Ok(r_type);
+        } else if r_expr.is_literal() && l_type.is_integer() {
+            self.expect_type(r_expr, &l_type, loc)?;
+            return Ok(l_type);
+        }
+        match l_type.common(&r_type) {
+            Some(val_type) => Ok(val_type),
+            None => Err(Diagnostic::error(loc, format!("cant mix `{:?}` and `{:?}` without a cast", l_type, r_type)).into()),
+        }
     }
 
-    fn comparison_check(&self, comparison: &Comparison, loc: (usize, usize)) -> Result<(), Box<dyn std::error::Error>> {
-        let l_type = self.value_type(&comparison.l_expr, loc.clone())?;
-        let r_type = self.value_type(&comparison.r_expr, loc.clone())?;
-        if l_type != r_type {
-            return Err(format!("{} expected `{:?}` but got `{:?}`", log_color(loc), l_type, r_type).into());
+    fn condition_check(&self, condition: &Value, loc: Loc) -> Result<(), Box<dyn std::error::Error>> {
+        let val_type = self.value_type(condition)?;
+        if val_type != Type::Bool {
+            return Err(Diagnostic::error(loc, format!("expected `Bool` but got `{:?}`", val_type)).into());
         }
-        return Ok(());
+        Ok(())
     }
 
     pub fn check(&mut self, ast: &Vec<Ast>, nested: bool) -> Result<(), Box<dyn std::error::Error>> {
         let mut local_vars: Vec<String> = Vec::new();
-        let mut index = 0;
+        let mut diagnostics = Diagnostics::new();
 
-        while index < ast.len() {
-            match &ast[index] {
-                Ast::Function {loc, name, param_t, return_t, body} => {
-                    if self.functions.get(name).is_some() {
-                        return Err(format!("{} function `{}` already exists", log_color(*loc), name).into());
-                    } else if param_t.len() > 6 {
-                        return Err(format!("{} functions can only accept up to 6 parameters", log_color(*loc)).into());
-                    } else if nested {
-                        return Err(format!("{} functions need to be global", log_color(*loc)).into());
-                    }
-                    for (var_name, var_type) in param_t {
-                        self.variables.insert(var_name.clone(), var_type.clone());
-                        local_vars.push(var_name.clone())
+        // a bad statement is reported and the next one is checked
+        for statement in ast {
+            diagnostics.collect(self.statement(statement, nested, &mut local_vars));
+        }
+        for var in local_vars {
+            // non fatal if fail
+            self.variables.remove(&var);
+        }
+        diagnostics.finish(())
+    }
+
+    fn statement(&mut self, statement: &Ast, nested: bool, local_vars: &mut Vec<String>) -> Result<(), Box<dyn std::error::Error>> {
+        match statement {
+            Ast::Function {loc, name, param_t, variadic, return_t, body} => {
+                // the body of a duplicate is still checked so its errors are reported as well
+                let duplicate = self.defined.get(name).map(|defined| {
+                    Diagnostic::error(*loc, format!("function `{}` already exists", name)).label(*defined, "previously defined here")
+                });
+                if nested {
+                    return Err(Diagnostic::error(*loc, "functions need to be global").into());
+                }
+                if return_t.is_aggregate() {
+                    return Err(Diagnostic::error(*loc, format!("function `{}` cant return `{:?}`", name, return_t)).help("use a pointer instead").into());
+                }
+                let mut names: Vec<&String> = Vec::new();
+                for (var_name, var_type) in param_t {
+                    if *var_type == Type::Void {
+                        return Err(Diagnostic::error(*loc, format!("parameter `{}` cant be of type `Void`", var_name)).into());
+                    } else if var_type.is_aggregate() {
+                        return Err(Diagnostic::error(*loc, format!("parameter `{}` cant be of type `{:?}`", var_name, var_type)).help("use a pointer instead").into());
+                    } else if names.contains(&var_name) {
+                        return Err(Diagnostic::error(*loc, format!("parameter `{}` already exists", var_name)).into());
                     }
-                    self.functions.insert(name.clone(), (return_t.clone(), param_t.clone()));
-                    self.current_fn = name.clone();
-                    self.check(body, true)?;
-                },
-                Ast::Return {loc, value} => {
-                    let return_t = self.functions.get(&self.current_fn).ok_or(format!("{} internal compiler error, current_fn not defined correctly", log_color(*loc)))?;
-                    if self.value_type(value, loc.clone())? != return_t.0 {
-                        return Err(format!("{} expected `{:?}` but got `{:?}`", log_color(*loc), return_t.0, self.value_type(value, loc.clone())?).into());
+                    names.push(var_name);
+                }
+                if variadic.as_ref().is_some_and(|variadic| names.contains(&variadic)) {
+                    return Err(Diagnostic::error(*loc, format!("parameter `{}` already exists", variadic.as_ref().unwrap())).into());
+                }
+                let previous = self.functions.insert(name.clone(), (return_t.clone(), param_t.clone(), variadic.is_some()));
+                self.defined.entry(name.clone()).or_insert(*loc);
+
+                // parameters shadow the globals with the same name until the end of the function
+                let mut shadowed: Vec<(String, Option<Type>)> = Vec::new();
+                for (var_name, var_type) in param_t {
+                    shadowed.push((var_name.clone(), self.variables.insert(var_name.clone(), var_type.clone())));
+                }
+                if let Some(variadic) = variadic {
+                    // the extra arguments are walked through a pointer
+                    shadowed.push((variadic.clone(), self.variables.insert(variadic.clone(), Type::TypedPtr(Box::new(Type::Int)))));
+                }
+                self.current_fn = name.clone();
+                let checked = self.check(body, true);
+                self.current_fn = String::new();
+                for (var_name, global) in shadowed.into_iter().rev() {
+                    match global {
+                        Some(global) => self.variables.insert(var_name, global),
+                        None => self.variables.remove(&var_name),
+                    };
+                }
+                // calls go to the first definition
+                if let Some(previous) = previous {
+                    self.functions.insert(name.clone(), previous);
+                }
+                let mut diagnostics = Diagnostics::new();
+                if let Some(duplicate) = duplicate {
+                    diagnostics.push(duplicate.into());
+                }
+                diagnostics.collect(checked);
+                diagnostics.finish(())?;
+            },
+            Ast::Return {loc, value} => {
+                let return_t = match self.functions.get(&self.current_fn) {
+                    Some(return_t) => return_t,
+                    None => {
+                        return Err(Diagnostic::error(*loc, "`return` outside of a function").into());
+                    },
+                };
+                self.expect_type(value, &return_t.0, *loc)?;
+            },
+            Ast::Variable {loc, name, var_t, value} => {
+                if *var_t == Type::Void {
+                    return Err(Diagnostic::error(*loc, format!("variable `{}` cant be of type `Void`", name)).into());
+                } else if self.variables.contains_key(name) {
+                    return Err(Diagnostic::error(*loc, format!("variable `{}` already exists", name)).into());
+                } else if !nested && !value.is_literal() && !matches!(value, Value::Null | Value::Bool(_) | Value::Str(_) | Value::Variant {..}) {
+                    // globals are static data so the value has to be known at compile time
+                    return Err(Diagnostic::error(*loc, format!("global `{}` needs a constant value", name)).into());
+                }
+                // variables without a value are zeroed
+                let checked = if matches!(value, Value::Null) { Ok(()) } else { self.expect_type(value, var_t, *loc) };
+                // the variable exists even with a bad value so its uses arent reported as well
+                self.variables.insert(name.clone(), var_t.clone());
+                local_vars.push(name.clone());
+                checked?;
+            },
+            Ast::MutateVar {loc, name, value} => {
+                let var = match self.variables.get(name) {
+                    Some(var) => var,
+                    None => {
+                        return Err(Diagnostic::error(*loc, format!("cant mutate non existing variable `{}`", name)).into());
+                    },
+                };
+                self.expect_type(value, var, *loc)?;
+            },
+            Ast::MutatePtr {loc, ptr_type, ptr, value} => {
+                if !self.value_type(ptr)?.is_pointer() {
+                    return Err(Diagnostic::error(*loc, format!("expected `Ptr` but got `{:?}`", self.value_type(ptr)?)).into());
+                }
+                self.expect_type(value, ptr_type, *loc)?;
+            },
+            Ast::Assign {loc, target, value} => {
+                self.lvalue_check(target, *loc)?;
+                let target_t = self.value_type(target)?;
+                self.expect_type(value, &target_t, *loc)?;
+            },
+            Ast::Compound {loc, target, op, value} => {
+                self.lvalue_check(target, *loc)?;
+                let target_t = self.value_type(target)?;
+                if target_t.is_pointer() && matches!(op, Operator::Plus | Operator::Minus) {
+                    let offset_t = self.value_type(value)?;
+                    if !offset_t.is_integer() {
+                        return Err(Diagnostic::error(*loc, format!("expected integer offset but got `{:?}`", offset_t)).into());
                     }
-                },
-                Ast::Variable {loc, name, var_t, value} => {
-                    if var_t.clone() != self.value_type(value, loc.clone())? {
-                        return Err(format!("{} expected `{:?}` but got `{:?}`", log_color(*loc), var_t, self.value_type(value, loc.clone())?).into());
-                    } else if self.variables.get(name).is_some() {
-                        return Err(format!("{} variable `{}` already exists", log_color(*loc), name).into());
+                } else if !target_t.is_integer() {
+                    return Err(Diagnostic::error(*loc, format!("`{:?}` can only be applied to integers but got `{:?}`", op, target_t)).into());
+                } else {
+                    self.operand_check(value, &target_t, op, *loc)?;
+                }
+            },
+            // declarations are checked by the resolver
+            Ast::Struct {..} | Ast::Enum {..} => {},
+            Ast::Const {loc, const_t, value, ..} => {
+                self.expect_type(value, const_t, *loc)?;
+            },
+            Ast::If {loc, condition, body, else_body} => {
+                // the bodies are checked even when the condition is bad
+                let mut diagnostics = Diagnostics::new();
+                diagnostics.collect(self.condition_check(condition, *loc));
+                diagnostics.collect(self.check(body, true));
+                diagnostics.collect(self.check(else_body, true));
+                return diagnostics.finish(());
+            },
+            Ast::While {loc, label, condition, body, step} => {
+                if label.is_some() && self.loops.contains(label) {
+                    return Err(Diagnostic::error(*loc, format!("loop label `{}` is already used by a enclosing loop", label.as_ref().unwrap())).into());
+                }
+                let mut diagnostics = Diagnostics::new();
+                diagnostics.collect(self.condition_check(condition, *loc));
+                self.loops.push(label.clone());
+                diagnostics.collect(self.check(body, true));
+                self.loops.pop();
+                diagnostics.collect(self.check(step, true));
+                return diagnostics.finish(());
+            },
+            Ast::Match {loc, value, arms, default} => {
+                // the arms are checked even when the value or a pattern is bad
+                let mut diagnostics = Diagnostics::new();
+                let val_type = self.value_type(value).and_then(|val_type| {
+                    if !val_type.is_integer() && !matches!(val_type, Type::Enum(_)) {
+                        return Err(Diagnostic::error(*loc, format!("cant match on `{:?}`, expected integer or enum", val_type)).into());
                     }
-                    self.variables.insert(name.clone(), var_t.clone());
-                    local_vars.push(name.clone());
-                },
-                Ast::MutateVar {loc, name, value} => {
-                    let var = self.variables.get(name);
-                    let val_type = self.value_type(value, loc.clone())?;
-                    if var.is_none() {
-                        return Err(format!("{} cant mutate non existing variable `{}`", log_color(*loc), name).into());
-                    } else if *var.unwrap() != val_type {
-                        return Err(format!("{} expected `{:?}` but got `{:?}`", log_color(*loc), var.unwrap(), val_type).into());
+                    Ok(val_type)
+                });
+                let val_type = diagnostics.collect(val_type);
+                let mut cases: Vec<usize> = Vec::new();
+                // a bad pattern would show up as a missing variant too
+                let mut complete = true;
+                for (patterns, body) in arms {
+                    for pattern in patterns.iter().filter(|_| val_type.is_some()) {
+                        match diagnostics.collect(self.pattern_check(pattern, val_type.as_ref().unwrap(), &cases, *loc)) {
+                            Some(case) => cases.push(case),
+                            None => complete = false,
+                        }
                     }
-                },
-                Ast::MutatePtr {loc, ptr_type, ptr, value} => {
-                    if self.value_type(ptr, loc.clone())? != Type::Ptr {
-                        return Err(format!("{} expected `Ptr` but got `{:?}`", log_color(*loc), self.value_type(ptr, loc.clone())?).into());
-                    } else if ptr_type != &self.value_type(value, loc.clone())? {
-                        return Err(format!("{} expected `{:?}` but got `{:?}`", log_color(*loc), ptr_type, self.value_type(value, loc.clone())?).into());
+                    diagnostics.collect(self.check(body, true));
+                }
+                if let Some(default) = default {
+                    diagnostics.collect(self.check(default, true));
+                } else if let (Some(Type::Enum(enum_t)), true) = (&val_type, complete) {
+                    // every variant needs a arm when there is no `_` arm
+                    let missing: Vec<String> = enum_t.variants.iter()
+                        .filter(|variant| !cases.contains(&variant.1))
+                        .map(|variant| format!("`{}::{}`", enum_t.name, variant.0))
+                        .collect();
+                    if !missing.is_empty() {
+                        diagnostics.push(Diagnostic::error(*loc, format!("match on `{}` is missing {}", enum_t.name, missing.join(", "))).into());
                     }
-                },
-                Ast::If {loc, comparison, body, else_body} => {
-                    self.comparison_check(&comparison, loc.clone())?;
-                    self.check(body, true)?;
-                    self.check(else_body, true)?;
-                },
-                Ast::While {loc, comparison, body} => {
-                    self.comparison_check(&comparison, loc.clone())?;
-                    self.check(body, true)?;
-                },
-                Ast::InlineAsm {..} => {},
-            }
-            index += 1;
-        }
-        for var in local_vars {
-            // non fatal if fail
-            self.variables.remove(&var);
+                }
+                return diagnostics.finish(());
+            },
+            Ast::Block {body, ..} => {
+                self.check(body, true)?;
+            },
+            Ast::Break {loc, label} | Ast::Continue {loc, label} => {
+                if self.loops.is_empty() {
+                    return Err(Diagnostic::error(*loc, format!("`{}` outside of a loop", if let Ast::Break {..} = statement { "break" } else { "continue" })).into());
+                } else if label.is_some() && !self.loops.contains(label) {
+                    return Err(Diagnostic::error(*loc, format!("unknown loop label `{}`", label.as_ref().unwrap())).into());
+                }
+            },
+            // includes, imports and `pub` are removed by the loader
+            Ast::InlineAsm {..} | Ast::Include {..} | Ast::Import {..} | Ast::Pub {..} => {},
+            Ast::Expr {value, ..} => {
+                // the result is thrown away so any type is allowed
+                self.value_type(value)?;
+            },
         }
-        return Ok(());
+        Ok(())
     }
 }
-
@@ -1,18 +1,105 @@
 import os
+import re
+import shutil
+import subprocess
 
-errors = (0, [])
-for f in os.listdir("./tests"):
-    file = os.path.join("./tests", f)
-    if os.path.isfile(file):
-        return_code = os.system(f"cargo run {file}")
-        if return_code != 0:
-            errors = (errors[0] + 1, file)
-    else:
-        print(f"[TESTS]: unknown file `{file}`")
-        exit(1)
-if errors[0] == 0:
-    print("[TESTS]: successfully ran all tests in `./tests`")
-else:
-    print(f"[TESTS]: failed with `{errors[0]}` error(s) in `{errors[1]}`")
+# every program in `./tests` is compiled, run and has to exit with 0, the files in
+# `./tests/lib` are only included by the tests. without fasm the programs are only
+# compiled and running them is reported as skipped. the programs in `./tests/errors`
+# have to fail to compile with every error written in a `# error: ... #` comment
+TESTS = "tests"
+ERRORS = os.path.join(TESTS, "errors")
+COMPILER = os.path.join("target", "debug", "langin")
+
+assemble = shutil.which("fasm") is not None
+
+if subprocess.run(["cargo", "build"]).returncode != 0:
+    print("[TESTS]: failed to build the compiler")
+    exit(1)
+if not assemble:
+    print("[TESTS]: `fasm` not found, the tests are compiled but not run")
+
+def generated(name, directory=TESTS):
+    return [os.path.join(directory, name), os.path.join(directory, name + ".asm")]
+
+def clean(name, directory=TESTS):
+    for path in generated(name, directory):
+        if os.path.exists(path):
+            os.remove(path)
 
+# the error of the test, `None` if it passed
+def run_test(name, flags):
+    file = os.path.join(TESTS, name + ".lang")
+    binary, asm = generated(name)
+    clean(name)
+    compiled = subprocess.run([COMPILER, file] + flags, capture_output=True, text=True)
 
+    # the compiler fails when it cant assemble but the assembly is written before that
+    if not assemble:
+        return None if os.path.exists(asm) else f"failed to compile:\n{compiled.stdout}"
+    if compiled.returncode != 0 or not os.path.exists(binary):
+        return f"failed to compile:\n{compiled.stdout}"
+    code = subprocess.run([binary]).returncode
+    if code != 0:
+        return f"exited with `{code}`"
+    return None
+
+def error_test(name):
+    file = os.path.join(ERRORS, name + ".lang")
+    compiled = subprocess.run([COMPILER, file, "--color=never", "--error-limit", "0"], capture_output=True, text=True)
+    clean(name, ERRORS)
+    if compiled.returncode == 0:
+        return "compiled without an error"
+    with open(file) as f:
+        expected = re.findall(r"# error: (.*?) #(?:\s*# underlined: (.*?) #)?", f.read())
+    # the message and the text under the `^` of every reported error
+    reported = []
+    for diagnostic in re.finditer(r"(?m)^error: (.*)$(?:\n *--> .*\n *\|\n *\d+ \| (.*)\n *\| ( *)(\^+))?", compiled.stdout):
+        message, line, indent, carets = diagnostic.groups(default="")
+        reported.append((message, line[len(indent):len(indent) + len(carets)]))
+    for error, underlined in expected:
+        found = next((diagnostic for diagnostic in reported if diagnostic[0] == error and underlined in ("", diagnostic[1])), None)
+        if found is None:
+            at = f" at `{underlined}`" if underlined else ""
+            return f"didnt report `{error}`{at}:\n{compiled.stdout}"
+        reported.remove(found)
+    return None
+
+errors = []
+skipped = 0
+for f in sorted(os.listdir(TESTS)):
+    name, extension = os.path.splitext(f)
+    if extension != ".lang":
+        continue
+    for flags in [[], ["-o"]]:
+        error = run_test(name, flags)
+        clean(name)
+        test = " ".join([f] + flags)
+        if error is not None:
+            print(f"[TESTS]: `{test}` {error}")
+            errors.append(test)
+        elif assemble:
+            print(f"[TESTS]: `{test}` ok")
+        else:
+            print(f"[TESTS]: `{test}` compiled, skipped running it")
+            skipped += 1
+
+for f in sorted(os.listdir(ERRORS)):
+    name, extension = os.path.splitext(f)
+    if extension != ".lang":
+        continue
+    error = error_test(name)
+    test = os.path.join("errors", f)
+    if error is None:
+        print(f"[TESTS]: `{test}` ok")
+    else:
+        print(f"[TESTS]: `{test}` {error}")
+        errors.append(test)
+
+if len(errors) != 0:
+    print(f"[TESTS]: failed with `{len(errors)}` error(s) in {', '.join(f'`{test}`' for test in errors)}")
+    exit(1)
+elif skipped != 0:
+    print(f"[TESTS]: skipped running `{skipped}` test(s) in `./tests`, install `fasm` to run them")
+else:
+    print("[TESTS]: successfully ran all tests in `./tests`")